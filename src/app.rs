@@ -1,19 +1,39 @@
 use crate::args::*;
+use crate::config::Config;
+use crate::controls::*;
+use crate::dbus::run_dbus_service;
 use crate::error::*;
+use crate::events::*;
+use crate::history::record_timer_end;
+use crate::mqtt::run_mqtt_bridge;
+use crate::process_guard::guard_processes;
+use crate::screen_lock::*;
+use crate::server::serve;
+use crate::sound::{list_output_devices, play_sound, test_sounds, SoundEvent};
+use crate::soundscape::Soundscape;
+use crate::strict_break::*;
+use crate::synth::AlarmPattern;
+use crate::tasks::*;
+use crate::team::{join_timer, JoinOptions};
 use crate::timer_info::DEFAULT_TIMER_DURATION;
-use crate::timer_info::{TimerInfo, TimerState};
+use crate::timer_info::{InterruptionKind, TimerInfo, TimerLock, TimerPhase, TimerState};
+use crate::tui::run_tui;
 use crate::utils::*;
+use crate::webhooks::send_webhooks;
+use clap::ValueEnum;
 use crossterm::cursor::{MoveToColumn, MoveToPreviousLine};
 use crossterm::execute;
 use crossterm::terminal::{Clear, ClearType};
-use lock::FailureReason;
 use notify_rust::{Notification, Timeout};
-use rodio::{Decoder, OutputStream, Sink};
+use std::io::{IsTerminal, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-/// Run the application with the given arguments
-pub fn run(args: &Cli) -> AppResult<()> {
+/// Run the application with the given arguments. Returns the exit code of the process.
+pub fn run(args: &Cli) -> AppResult<i32> {
     match &args.subcmd {
         SubCommand::Start {
             duration,
@@ -24,56 +44,210 @@ pub fn run(args: &Cli) -> AppResult<()> {
             wait,
             resume,
             lock_screen,
+            break_time,
+            events,
+            task,
         } => {
-            start_timer(
-                parse_duration(duration.clone()),
-                parse_duration(add.clone()),
-                message.clone().unwrap_or("".to_string()),
-                *silent,
-                *notify,
-                *resume,
-                *lock_screen,
-            )?;
-            if *wait || *lock_screen {
-                wait_for_timer()?;
+            if let Some(query) = task {
+                let config = Config::from_file_or_default()?;
+                pick_task(&config.tasks, query.as_deref())?;
+            }
+            start_timer(StartOptions {
+                duration: parse_duration(duration.clone()),
+                add: parse_duration(add.clone()),
+                message: message.clone().unwrap_or("".to_string()),
+                silent: *silent,
+                notify: *notify,
+                resume: *resume,
+                lock_screen: *lock_screen,
+                phase: if *break_time {
+                    TimerPhase::Break
+                } else {
+                    TimerPhase::Work
+                },
+                ..Default::default()
+            })?;
+            if *wait || *lock_screen || events.is_some() {
+                wait_for_timer(WaitOutput::resolve(*events))?;
+            }
+        }
+        SubCommand::Attach { events } => {
+            let timer_info = TimerInfo::from_file_or_default()?;
+            if !TimerInfo::info_file_exists() || timer_info.state == TimerState::Finished {
+                println!("No timer to attach to.");
+                return Ok(WaitOutcome::Stopped.exit_code());
             }
+            return Ok(wait_for_timer(WaitOutput::resolve(*events))?.exit_code());
         }
         SubCommand::Pause => {
             pause_timer()?;
         }
+        SubCommand::Interrupt {
+            internal: _,
+            external,
+            note,
+        } => {
+            let kind = if *external {
+                InterruptionKind::External
+            } else {
+                InterruptionKind::Internal
+            };
+            let timer_info =
+                TimerInfo::record_interruption(kind, note.clone().unwrap_or_default())?;
+            publish_event(EventKind::Interrupted, &timer_info);
+            println!(
+                "Interruption recorded ({} in this session).",
+                timer_info.interruptions.len()
+            );
+        }
         SubCommand::Stop => {
             stop_timer()?;
         }
         SubCommand::Status {
             format,
             time_format,
+            follow,
         } => {
-            let status = get_status(*format, *time_format)?;
-            println!("{}", status);
+            if *follow {
+                follow_status(*format, *time_format)?;
+            } else {
+                let status = get_status(*format, *time_format)?;
+                println!("{}", status);
+            }
         }
+        SubCommand::Lock { grace, cancel } => {
+            if *cancel {
+                if cancel_pending_lock()? {
+                    println!("Screen lock cancelled.");
+                } else {
+                    println!("No pending screen lock.");
+                }
+            } else {
+                let config = Config::from_file_or_default()?;
+                let grace_time = parse_duration(grace.clone()).unwrap_or(config.lock.grace_time);
+                lock_screen_after_grace(&config.lock, grace_time)?;
+            }
+        }
+        SubCommand::Tui => {
+            run_tui()?;
+        }
+        SubCommand::TriggerAlarm => {
+            trigger_alarm(&TimerInfo::from_file_or_default()?)?;
+        }
+        SubCommand::Subscribe { socket } => match socket {
+            Some(path) => serve_events(&path.clone().unwrap_or_else(get_event_socket_file))?,
+            None => subscribe()?,
+        },
+        SubCommand::Serve { port, bind, token } => {
+            serve(bind, *port, token.as_deref())?;
+        }
+        SubCommand::Join {
+            host,
+            silent,
+            notify,
+            token,
+        } => {
+            join_timer(
+                host,
+                JoinOptions {
+                    silent: *silent,
+                    notify: *notify,
+                },
+                token.as_deref(),
+            )?;
+        }
+        SubCommand::Dbus => {
+            run_dbus_service()?;
+        }
+        SubCommand::Mqtt => {
+            run_mqtt_bridge()?;
+        }
+        SubCommand::GuardProcesses => {
+            guard_processes()?;
+        }
+        SubCommand::SendWebhooks { event } => {
+            send_webhooks(event)?;
+        }
+        SubCommand::Skip => {
+            skip_break()?;
+        }
+        SubCommand::EnforceBreak => {
+            enforce_break()?;
+        }
+        SubCommand::Task { action } => run_task_command(action)?,
+        SubCommand::Sound { action } => match action {
+            SoundCommand::Test => {
+                test_sounds(&Config::from_file_or_default()?.sound)?;
+            }
+            SoundCommand::Patterns => {
+                for pattern in AlarmPattern::value_variants() {
+                    println!("{}", pattern.name());
+                }
+            }
+            SoundCommand::Devices => {
+                let (devices, default) = list_output_devices()?;
+                for device in devices {
+                    if default.as_ref() == Some(&device) {
+                        println!("{} (default)", device);
+                    } else {
+                        println!("{}", device);
+                    }
+                }
+            }
+            SoundCommand::Render { pattern, output } => {
+                pattern.render_to_wav(output)?;
+                println!(
+                    "Rendered {} pattern to {}",
+                    pattern.name(),
+                    output.display()
+                );
+            }
+        },
     }
-    Ok(())
+    Ok(0)
+}
+
+/// Options for starting (or resuming) the timer
+#[derive(Debug, Default)]
+pub struct StartOptions {
+    pub duration: Option<i64>,
+    pub add: Option<i64>,
+    pub message: String,
+    pub silent: bool,
+    pub notify: bool,
+    pub resume: bool,
+    pub lock_screen: bool,
+    pub phase: TimerPhase,
+    pub strict: bool,
 }
 
 /// Start the timer. If the timer is already running, the duration is added to the current duration.
-pub fn start_timer(
-    duration: Option<i64>,
-    add: Option<i64>,
-    message: String,
-    silent: bool,
-    notify: bool,
-    resume: bool,
-    lock_screen: bool,
-) -> AppResult<()> {
+pub fn start_timer(options: StartOptions) -> AppResult<()> {
+    let StartOptions {
+        duration,
+        add,
+        message,
+        silent,
+        notify,
+        resume,
+        lock_screen,
+        phase,
+        strict,
+    } = options;
+    let _lock = TimerLock::acquire()?;
     let mut timer_info = TimerInfo::from_file_or_default()?;
-    if timer_info.is_running() && add.is_some() {
+    ensure_not_in_strict_break(&timer_info)?;
+    let mut events = Vec::new();
+    if let Some(add) = add.filter(|_| timer_info.is_running()) {
         // Add more time to the timer
-        timer_info.duration += add.unwrap();
+        timer_info.duration += add;
+        timer_info.warned = false;
+        events.push(EventKind::TimeAdded);
     } else if timer_info.is_paused() && resume {
         // Resume a paused timer
         let now = chrono::Utc::now().timestamp();
         let elapsed = timer_info.pause_time - timer_info.start_time;
-        timer_info.duration = timer_info.duration - elapsed;
+        timer_info.duration -= elapsed;
         timer_info.start_time = now;
         timer_info.pause_time = now;
         timer_info.message = timer_info.message.clone();
@@ -81,73 +255,161 @@ pub fn start_timer(
         timer_info.notify = timer_info.notify || notify;
         timer_info.lock_screen = timer_info.lock_screen || lock_screen;
         timer_info.state = TimerState::Running;
+        events.push(EventKind::Resumed);
     } else {
         // Start a new timer
+        record_timer_end(&mut timer_info)?;
+        if let Some(event) = end_event(&timer_info).filter(|_| TimerInfo::info_file_exists()) {
+            publish_event(event, &timer_info);
+        }
+        if TimerInfo::info_file_exists() && timer_info.phase != phase {
+            events.push(EventKind::PhaseChanged);
+        }
+        events.push(EventKind::Started);
         let duration = duration.unwrap_or(add.unwrap_or(DEFAULT_TIMER_DURATION));
         let now = chrono::Utc::now().timestamp() + 1;
         timer_info.duration = duration;
         timer_info.start_time = now;
+        timer_info.session_start = now;
         timer_info.pause_time = now;
         timer_info.message = message;
         timer_info.silent = silent;
         timer_info.notify = notify;
         timer_info.state = TimerState::Running;
         timer_info.lock_screen = lock_screen;
+        timer_info.phase = phase;
+        timer_info.warned = false;
+        timer_info.strict = strict;
+        timer_info.interruptions = Vec::new();
+        // Linking the task is optional, so the timer is started even if the task is not found
+        timer_info.task = match phase {
+            TimerPhase::Work => selected_task().unwrap_or_else(|e| {
+                eprintln!("Warning: Cannot link the selected task: {}", e);
+                None
+            }),
+            TimerPhase::Break => None,
+        };
     }
     timer_info.write_to_file()?;
+    for event in events {
+        publish_event(event, &timer_info);
+    }
     Ok(())
 }
 
+/// Add time to the running or paused timer. A negative value removes time, but the timer is not
+/// shortened below the time already elapsed.
+pub fn add_time(seconds: i64) -> AppResult<()> {
+    let mut timer_info = TimerInfo::from_file_or_default()?;
+    if timer_info.state == TimerState::Finished {
+        return Ok(());
+    }
+    ensure_not_in_strict_break(&timer_info)?;
+    timer_info.duration = (timer_info.duration + seconds).max(timer_info.get_time_elapsed());
+    if seconds > 0 {
+        timer_info.warned = false;
+    }
+    timer_info.write_to_file()?;
+    publish_event(EventKind::TimeAdded, &timer_info);
+    Ok(())
+}
+
+/// Replace the message of the timer.
+pub fn set_message(message: String) -> AppResult<()> {
+    let mut timer_info = TimerInfo::from_file_or_default()?;
+    timer_info.message = message;
+    timer_info.write_to_file()
+}
+
+/// Ask for a new timer message on the terminal. An empty answer keeps the current message.
+fn prompt_message() -> AppResult<()> {
+    let mut stdout = std::io::stdout();
+    print!("Message: ");
+    stdout.flush()?;
+    let mut message = String::new();
+    std::io::stdin().read_line(&mut message)?;
+    execute!(
+        stdout,
+        MoveToPreviousLine(1),
+        Clear(ClearType::CurrentLine),
+        MoveToColumn(0),
+    )?;
+    let message = message.trim();
+    if message.is_empty() {
+        return Ok(());
+    }
+    set_message(message.to_string())
+}
+
 /// Pause the timer. If the timer is already paused, the timer is resumed.
 pub fn pause_timer() -> AppResult<()> {
     let mut timer_info = TimerInfo::from_file_or_default()?;
     if timer_info.is_paused() {
-        start_timer(
-            Some(timer_info.duration),
-            None,
-            timer_info.message,
-            timer_info.silent,
-            timer_info.notify,
-            true,
-            timer_info.lock_screen,
-        )?;
+        start_timer(StartOptions {
+            duration: Some(timer_info.duration),
+            message: timer_info.message,
+            silent: timer_info.silent,
+            notify: timer_info.notify,
+            resume: true,
+            lock_screen: timer_info.lock_screen,
+            phase: timer_info.phase,
+            ..Default::default()
+        })?;
     } else if timer_info.is_running() {
+        ensure_not_in_strict_break(&timer_info)?;
         let now = chrono::Utc::now().timestamp();
         timer_info.pause_time = now;
         timer_info.state = TimerState::Paused;
         timer_info.write_to_file()?;
+        publish_event(EventKind::Paused, &timer_info);
     }
     Ok(())
 }
 
+/// Return the event which is published when the timer ends now
+fn end_event(timer_info: &TimerInfo) -> Option<EventKind> {
+    match timer_info.state {
+        TimerState::Running if timer_info.is_time_run_out() => Some(EventKind::Finished),
+        TimerState::Running | TimerState::Paused => Some(EventKind::Stopped),
+        TimerState::Finished => None,
+    }
+}
+
 /// Stop the timer.
 pub fn stop_timer() -> AppResult<()> {
+    let _lock = TimerLock::acquire()?;
     let mut timer_info = TimerInfo::from_file_or_default()?;
+    ensure_not_in_strict_break(&timer_info)?;
+    record_timer_end(&mut timer_info)?;
+    let event = end_event(&timer_info);
     timer_info.state = TimerState::Finished;
     timer_info.write_to_file()?;
+    if let Some(event) = event {
+        publish_event(event, &timer_info);
+    }
     Ok(())
 }
 
-/// Lock the screen.
-fn lock_screen() -> AppResult<()> {
-    println!("Locking screen...");
-
-    lock::lock().map_err(|fail| {
-        AppError::new(match fail {
-            FailureReason::CannotExecute => "Cannot execute the lock command.",
-            FailureReason::LinuxCommandNotFound => {
-                "Linux command not found. The following commands are supported\
-                    \n- xdg-screensaver\
-                    \n- gnome-screensaver\
-                    \n- dm-tool"
-            }
-        })
-    })
+/// End the timer if its time has run out. Returns the timer as it was before it ended, or `None`
+/// if the timer is not running out (e.g. another command has already ended it). Only the caller
+/// which gets the timer records the end and triggers the alarm.
+pub fn finish_timer() -> AppResult<Option<TimerInfo>> {
+    let _lock = TimerLock::acquire()?;
+    let mut timer_info = TimerInfo::from_file_or_default()?;
+    if !timer_info.is_running() || !timer_info.is_time_run_out() {
+        return Ok(None);
+    }
+    let finished = timer_info.clone();
+    record_timer_end(&mut timer_info)?;
+    timer_info.state = TimerState::Finished;
+    timer_info.write_to_file()?;
+    publish_event(EventKind::Finished, &timer_info);
+    Ok(Some(finished))
 }
 
 /// Trigger the alarm sound and/or the system notification.
 pub fn trigger_alarm(timer_info: &TimerInfo) -> AppResult<()> {
-    println!("Time is up!");
+    eprintln!("Time is up!");
 
     if timer_info.notify {
         let mut path = String::from("dialog-warning");
@@ -164,28 +426,76 @@ pub fn trigger_alarm(timer_info: &TimerInfo) -> AppResult<()> {
     }
 
     if !timer_info.silent {
-        let (_stream, stream_handle) = OutputStream::try_default()?;
-        let sink = Sink::try_new(&stream_handle).unwrap();
-        if let Some(path) = get_custom_alarm_file() {
-            let file = std::fs::File::open(path)?;
-            let source = Decoder::new(file)?;
-            sink.append(source);
-        } else {
-            let mp3 = include_bytes!("../assets/ding.mp3");
-            let source = Decoder::new(std::io::Cursor::new(mp3))?;
-            sink.append(source);
-        }
-        sink.set_volume(1.0);
-        sink.sleep_until_end();
-        sink.clear();
+        let event = match timer_info.phase {
+            TimerPhase::Work => SoundEvent::WorkEnd,
+            TimerPhase::Break => SoundEvent::BreakEnd,
+        };
+        play_sound(event, &Config::from_file_or_default()?.sound)?;
+    }
+
+    // A finished work timer starts the strict break which takes care of locking the screen
+    let config = Config::from_file_or_default()?;
+    if config.strict_break.enabled && timer_info.phase == TimerPhase::Work {
+        return start_strict_break(timer_info, &config);
     }
 
     // Now check if the lock screen option is enabled
     if timer_info.lock_screen {
-        lock_screen()?;
+        let grace_time = config.lock.grace_time_for(timer_info.phase);
+        if grace_time > 0 {
+            // Count down in a detached process so that the caller (e.g. `status`) is not blocked
+            eprintln!(
+                "Locking screen in {}s (run `pomodoro-cli lock --cancel` to cancel)",
+                grace_time
+            );
+            std::process::Command::new(std::env::current_exe()?)
+                .args(["lock", "--grace", &format!("{}s", grace_time)])
+                .stdout(std::process::Stdio::null())
+                .spawn()?;
+        } else {
+            lock_screen(&config.lock)?;
+        }
     }
 
-    return Ok(());
+    Ok(())
+}
+
+/// Trigger the alarm of the timer which was just stopped in a separate process, so that the
+/// caller keeps responding while the sound plays. The messages of the alarm are written to the
+/// given `stderr`.
+pub fn spawn_alarm(stderr: Stdio) -> AppResult<Child> {
+    Ok(Command::new(std::env::current_exe()?)
+        .arg("trigger-alarm")
+        .stdout(Stdio::null())
+        .stderr(stderr)
+        .spawn()?)
+}
+
+/// Return the status text of the timer in the given format
+fn format_status(
+    timer_info: &TimerInfo,
+    format: Option<StatusFormat>,
+    time_format: Option<TimeFormat>,
+) -> AppResult<String> {
+    Ok(match format {
+        Some(StatusFormat::Json) => timer_info.get_json_info(time_format.unwrap_or_default())?,
+        _ => timer_info.get_human_readable(time_format.unwrap_or_default()),
+    })
+}
+
+/// Trigger the alarm in a separate process if the time has run out, or give the warning if it
+/// is due. Used by the long-running commands, which must not block while the alarm plays.
+pub fn check_timer_detached() -> AppResult<()> {
+    let timer_info = TimerInfo::from_file_or_default()?;
+    if timer_info.is_running() && !timer_info.wait && timer_info.is_time_run_out() {
+        if finish_timer()?.is_some() {
+            let mut alarm = spawn_alarm(Stdio::inherit())?;
+            thread::spawn(move || alarm.wait());
+        }
+    } else if !timer_info.wait {
+        check_warning(timer_info)?;
+    }
+    Ok(())
 }
 
 /// Return the status of the timer in the given format.
@@ -194,69 +504,251 @@ pub fn get_status(
     time_format: Option<TimeFormat>,
 ) -> AppResult<String> {
     let timer_info = TimerInfo::from_file_or_default()?;
-    let status: String = match format {
-        Some(StatusFormat::Json) => {
-            format!(
-                "{}",
-                timer_info.get_json_info(time_format.unwrap_or_default())?
-            )
-        }
-        _ => {
-            format!(
-                "{}",
-                timer_info.get_human_readable(time_format.unwrap_or_default())
-            )
-        }
-    };
+    let status = format_status(&timer_info, format, time_format)?;
 
     if timer_info.is_running() && !timer_info.wait && timer_info.is_time_run_out() {
-        stop_timer()?;
-        trigger_alarm(&timer_info)?;
+        if let Some(finished) = finish_timer()? {
+            trigger_alarm(&finished)?;
+        }
+    } else if !timer_info.wait {
+        check_warning(timer_info)?;
     }
     Ok(status)
 }
 
-/// Wait for the timer to finish.
-pub fn wait_for_timer() -> AppResult<()> {
+/// Print the status every second and immediately when the timer is changed by another command
+/// (e.g. for a long-running Waybar `exec`). Returns when the output is closed.
+pub fn follow_status(
+    format: Option<StatusFormat>,
+    time_format: Option<TimeFormat>,
+) -> AppResult<()> {
+    let modified_time = || {
+        std::fs::metadata(get_timer_info_file())
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    let mut stdout = std::io::stdout();
+    loop {
+        let status = format_status(&TimerInfo::from_file_or_default()?, format, time_format)?;
+        // The alarm is played in a separate process so that the stream keeps going meanwhile
+        check_timer_detached()?;
+        let last_modified = modified_time();
+        if let Err(e) = writeln!(stdout, "{}", status).and_then(|_| stdout.flush()) {
+            if e.kind() == std::io::ErrorKind::BrokenPipe {
+                return Ok(());
+            }
+            return Err(e.into());
+        }
+
+        let next_tick = std::time::Instant::now() + Duration::from_secs(1);
+        while std::time::Instant::now() < next_tick && modified_time() == last_modified {
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+/// Play the warning sound if the timer is about to finish. The warning is given only once per
+/// timer.
+pub fn check_warning(mut timer_info: TimerInfo) -> AppResult<()> {
+    let config = Config::from_file_or_default()?;
+    if timer_info.is_warning_due(config.sound.warning_time) {
+        timer_info.warned = true;
+        timer_info.write_to_file()?;
+        publish_event(EventKind::Warning, &timer_info);
+        if !timer_info.silent {
+            play_sound(SoundEvent::Warning, &config.sound)?;
+        }
+    }
+    Ok(())
+}
+
+/// Defines how waiting for the timer ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WaitOutcome {
+    /// The time ran out
+    Finished,
+    /// The timer was stopped by another command (or there was no timer to wait for)
+    Stopped,
+    /// Waiting was interrupted (e.g. with Ctrl-C) without changing the timer
+    Interrupted,
+}
+
+impl WaitOutcome {
+    /// Return the process exit code of the outcome
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Finished => 0,
+            Self::Stopped => 2,
+            Self::Interrupted => 130,
+        }
+    }
+}
+
+/// Defines how the progress is reported while waiting for the timer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WaitOutput {
+    /// Progress bar which is redrawn every second (requires a terminal)
+    Progress,
+    /// One line per minute and on every state change
+    Plain,
+    /// JSON line events
+    Json,
+}
+
+impl WaitOutput {
+    /// Return the requested output. Defaults to the progress bar on a terminal and to plain
+    /// lines otherwise (e.g. when piped to a file or run as a service).
+    pub fn resolve(events: Option<EventsFormat>) -> Self {
+        match events {
+            Some(EventsFormat::Json) => Self::Json,
+            Some(EventsFormat::Plain) => Self::Plain,
+            None if std::io::stdout().is_terminal() => Self::Progress,
+            None => Self::Plain,
+        }
+    }
+}
+
+/// Print the event as a JSON line if the JSON output is used
+fn emit_event(output: WaitOutput, event: EventKind, timer_info: &TimerInfo) -> AppResult<()> {
+    if output == WaitOutput::Json {
+        println!("{}", TimerEvent::new(event, timer_info).to_json()?);
+        std::io::stdout().flush()?;
+    }
+    Ok(())
+}
+
+/// Wait for the timer to finish. A paused timer is waited for until it is resumed or stopped.
+pub fn wait_for_timer(output: WaitOutput) -> AppResult<WaitOutcome> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = interrupted.clone();
+    ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst))
+        .map_err(|e| AppError::new(&format!("Cannot set the signal handler: {}", e)))?;
+
+    // Keyboard controls are available only with the progress bar on a terminal
+    let interactive = output == WaitOutput::Progress
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal();
+
     // This thread will wait for the timer to finish and peridoically prints the time left.
-    let timer_thrd = thread::spawn(move || -> AppResult<()> {
+    let timer_thrd = thread::spawn(move || -> AppResult<WaitOutcome> {
         let mut stdout = std::io::stdout();
+        let config = Config::from_file_or_default()?;
+        let soundscape = Soundscape::new(&config.soundscape, config.sound.device.as_deref())?;
+        let mut raw_mode = None;
+        if interactive {
+            println!("{}", CONTROLS_HELP);
+            raw_mode = Some(RawModeGuard::new()?);
+        }
+        let use_color = std::env::var_os("NO_COLOR").is_none();
+        let mut notice = String::new();
+        let mut last_state = None;
+        let mut last_minute = None;
         loop {
             let timer_info = TimerInfo::from_file_or_default()?;
-            let percentage = (timer_info.get_percentage() / 4.0) as i64;
-            print!("|");
-            for _ in 0..percentage {
-                print!("#");
+            if let Some(soundscape) = &soundscape {
+                soundscape.set_playing(
+                    timer_info.is_running()
+                        && timer_info.phase == TimerPhase::Work
+                        && !timer_info.is_time_run_out(),
+                );
             }
-            for _ in 0..(25 - percentage) {
-                print!("-");
+            match output {
+                WaitOutput::Progress => {
+                    let (columns, _) = crossterm::terminal::size().unwrap_or((80, 24));
+                    let columns = (columns as usize).saturating_sub(notice.chars().count());
+                    println!(
+                        "{}{}",
+                        config.progress_bar.render(&timer_info, columns, use_color),
+                        notice
+                    );
+                }
+                WaitOutput::Plain => {
+                    let minute = timer_info.get_time_left().max(0) / 60;
+                    if last_state != Some(timer_info.state) || last_minute != Some(minute) {
+                        println!(
+                            "{} {}",
+                            chrono::Local::now().format("%H:%M:%S"),
+                            timer_info.get_human_readable(TimeFormat::default())
+                        );
+                        stdout.flush()?;
+                    }
+                    last_minute = Some(minute);
+                }
+                WaitOutput::Json => {
+                    if let Some(event) =
+                        last_state.and_then(|state| state_change_event(state, timer_info.state))
+                    {
+                        emit_event(output, event, &timer_info)?;
+                    }
+                    emit_event(output, EventKind::Tick, &timer_info)?;
+                }
             }
-            println!("| {}", timer_info.get_human_readable(TimeFormat::default()));
-
-            thread::sleep(std::time::Duration::from_millis(1000));
-            execute!(
-                stdout,
-                MoveToPreviousLine(1),
-                Clear(ClearType::CurrentLine),
-                MoveToColumn(0),
-            )?;
+            last_state = Some(timer_info.state);
 
-            if !timer_info.is_running() {
-                stop_timer()?;
-                break;
+            let control = if interactive {
+                wait_for_control(Duration::from_millis(1000))?
+            } else {
+                thread::sleep(Duration::from_millis(1000));
+                None
+            };
+            if output == WaitOutput::Progress {
+                execute!(
+                    stdout,
+                    MoveToPreviousLine(1),
+                    Clear(ClearType::CurrentLine),
+                    MoveToColumn(0),
+                )?;
             }
 
-            if timer_info.is_time_run_out() {
-                stop_timer()?;
-                trigger_alarm(&timer_info)?;
-                break;
+            if interrupted.load(Ordering::SeqCst) || control == Some(Control::Detach) {
+                emit_event(output, EventKind::Detached, &timer_info)?;
+                return Ok(WaitOutcome::Interrupted);
             }
+
+            if let Some(control) = control {
+                notice.clear();
+                let result = match control {
+                    Control::PauseResume => pause_timer(),
+                    Control::Stop => stop_timer(),
+                    Control::AddTime(seconds) => add_time(seconds),
+                    Control::EditMessage => {
+                        drop(raw_mode.take());
+                        let result = prompt_message();
+                        raw_mode = Some(RawModeGuard::new()?);
+                        result
+                    }
+                    Control::Detach => Ok(()),
+                };
+                if let Err(e) = result {
+                    notice = format!(" ({})", e);
+                }
+            }
+
+            let timer_info = TimerInfo::from_file_or_default()?;
+            if timer_info.state == TimerState::Finished {
+                emit_event(output, EventKind::Stopped, &timer_info)?;
+                return Ok(WaitOutcome::Stopped);
+            }
+
+            if timer_info.is_running() && timer_info.is_time_run_out() {
+                if let Some(soundscape) = &soundscape {
+                    soundscape.set_playing(false);
+                }
+                drop(raw_mode.take());
+                let finished = finish_timer()?;
+                emit_event(output, EventKind::Finished, &timer_info)?;
+                // The alarm has already been triggered if another command ended the timer
+                if let Some(finished) = finished {
+                    trigger_alarm(&finished)?;
+                }
+                return Ok(WaitOutcome::Finished);
+            }
+            check_warning(timer_info)?;
         }
-        return Ok(());
     });
 
-    if let Err(e) = timer_thrd.join() {
-        return Err(AppError::new(&format!("Error: {:?}", e)));
+    match timer_thrd.join() {
+        Ok(outcome) => outcome,
+        Err(e) => Err(AppError::new(&format!("Error: {:?}", e))),
     }
-    return Ok(());
 }
@@ -1,5 +1,7 @@
+use crate::synth::AlarmPattern;
 use clap::{Parser, Subcommand};
 use serde::Serialize;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -55,11 +57,57 @@ pub enum SubCommand {
             help = "Lock the screen when the timer finishes"
         )]
         lock_screen: bool,
+
+        #[arg(
+            long = "break",
+            default_value_t = false,
+            conflicts_with_all = &["add", "resume"],
+            help = "Start a break timer instead of a work timer"
+        )]
+        break_time: bool,
+
+        #[arg(
+            long,
+            help = "Wait for the timer and report the progress as plain lines or JSON events"
+        )]
+        events: Option<EventsFormat>,
+
+        #[arg(
+            long,
+            value_name = "QUERY",
+            num_args = 0..=1,
+            conflicts_with_all = &["add", "resume", "break_time"],
+            help = "Link the session to the task which matches the query (defaults to the selected or top-priority task)"
+        )]
+        task: Option<Option<String>>,
     },
     /// Stop the timer
     Stop,
+    /// Attach to the current timer and wait for it to finish. Exits with 0 when the time runs
+    /// out, 2 when the timer is stopped by another command and 130 when interrupted.
+    #[command(alias = "wait")]
+    Attach {
+        #[arg(
+            long,
+            help = "Report the progress as plain lines or JSON events instead of a progress bar"
+        )]
+        events: Option<EventsFormat>,
+    },
     /// Pause/Resume the timer
     Pause,
+    /// Record an interruption of the running work session. A session which is stopped after an
+    /// interruption is recorded as void.
+    #[command(group = clap::ArgGroup::new("kind").required(true).args(["internal", "external"]))]
+    Interrupt {
+        #[arg(long, help = "Self-inflicted interruption (e.g. checking the chat)")]
+        internal: bool,
+
+        #[arg(long, help = "Interruption caused by someone else (e.g. a colleague)")]
+        external: bool,
+
+        #[arg(help = "Note about the interruption")]
+        note: Option<String>,
+    },
     /// Get the current status of the timer
     Status {
         #[arg(short, long, help = "Status format")]
@@ -67,6 +115,183 @@ pub enum SubCommand {
 
         #[arg(short, long, help = "Time format")]
         time_format: Option<TimeFormat>,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Keep running and print the status every second and whenever the timer changes"
+        )]
+        follow: bool,
+    },
+    /// Print the lifecycle events of the timer as JSON lines
+    Subscribe {
+        #[arg(
+            long,
+            value_name = "PATH",
+            num_args = 0..=1,
+            help = "Serve the events on a Unix socket instead (defaults to the runtime directory)"
+        )]
+        socket: Option<Option<PathBuf>>,
+    },
+    /// Serve a REST API and a web dashboard for controlling the timer
+    #[command(alias = "host")]
+    Serve {
+        #[arg(short, long, default_value_t = 8080, help = "Port to listen on")]
+        port: u16,
+
+        #[arg(
+            long,
+            default_value = "127.0.0.1",
+            help = "Address to listen on (use 0.0.0.0 to allow access from the network)"
+        )]
+        bind: String,
+
+        #[arg(
+            long,
+            help = "Token which API requests must send as 'Authorization: Bearer <token>' (required for network addresses)"
+        )]
+        token: Option<String>,
+    },
+    /// Mirror the timer of a host started with `serve`
+    Join {
+        #[arg(help = "Address of the host (e.g. '192.168.1.2:8080')")]
+        host: String,
+
+        #[arg(long, default_value_t = false, help = "Do not play the alarm sound")]
+        silent: bool,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            help = "Show a notification when the timer finishes"
+        )]
+        notify: bool,
+
+        #[arg(long, help = "Token of the host (see `serve --token`)")]
+        token: Option<String>,
+    },
+    /// Publish the timer state to the configured MQTT broker and accept commands
+    Mqtt,
+    /// Serve the timer as a D-Bus service (org.pomodorocli.Timer) on the session bus
+    Dbus,
+    /// Scan for distracting processes while the work timer is running (spawned on timer events)
+    #[command(hide = true)]
+    GuardProcesses,
+    /// Send the event to the configured webhooks (spawned on timer events)
+    #[command(hide = true)]
+    SendWebhooks { event: String },
+    /// Lock the screen after the grace period
+    Lock {
+        #[arg(
+            short,
+            long,
+            help = "Grace period before locking ('30s'). Defaults to the configured grace time"
+        )]
+        grace: Option<String>,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with = "grace",
+            help = "Cancel the pending screen lock"
+        )]
+        cancel: bool,
+    },
+    /// Open the full-screen dashboard
+    Tui,
+    /// Trigger the alarm of the finished timer (spawned by the long-running commands)
+    #[command(hide = true)]
+    TriggerAlarm,
+    /// Skip the strict break using one of the daily emergency skips
+    Skip,
+    /// Keep the screen locked until the strict break is over
+    #[command(hide = true)]
+    EnforceBreak,
+    /// Manage the tasks which work sessions are linked to
+    Task {
+        #[clap(subcommand)]
+        action: TaskCommand,
+    },
+    /// Manage the alarm sounds
+    Sound {
+        #[clap(subcommand)]
+        action: SoundCommand,
+    },
+}
+
+/// Defines the subcommands for the task command
+#[derive(Subcommand, Debug)]
+pub enum TaskCommand {
+    /// Add a new task
+    Add {
+        #[arg(help = "Title of the task")]
+        title: String,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 0,
+            help = "Estimated number of pomodoros"
+        )]
+        estimate: u32,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = false,
+            help = "Select the task for the next work sessions"
+        )]
+        select: bool,
+    },
+    /// List the open tasks
+    List {
+        #[arg(short, long, default_value_t = false, help = "Include the done tasks")]
+        all: bool,
+    },
+    /// Mark a task as done
+    Done {
+        #[arg(help = "ID of the task (defaults to the selected task)")]
+        id: Option<u32>,
+    },
+    /// Set the estimated number of pomodoros of a task
+    Estimate {
+        #[arg(help = "ID of the task")]
+        id: u32,
+
+        #[arg(help = "Estimated number of pomodoros")]
+        pomodoros: u32,
+    },
+    /// Select the task which the next work sessions are linked to
+    Select {
+        #[arg(required_unless_present = "clear", help = "ID of the task")]
+        id: Option<u32>,
+
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with = "id",
+            help = "Clear the selection"
+        )]
+        clear: bool,
+    },
+}
+
+/// Defines the subcommands for the sound command
+#[derive(Subcommand, Debug)]
+pub enum SoundCommand {
+    /// Play each configured sound
+    Test,
+    /// List the built-in alarm patterns
+    Patterns,
+    /// List the available audio output devices
+    Devices,
+    /// Render a built-in alarm pattern into a WAV file
+    Render {
+        #[arg(help = "Name of the pattern")]
+        pattern: AlarmPattern,
+
+        #[arg(help = "Path of the output WAV file")]
+        output: PathBuf,
     },
 }
 
@@ -79,6 +304,13 @@ pub enum StatusFormat {
     Json,
 }
 
+/// Defines the output format of the progress while waiting for the timer
+#[derive(clap::ValueEnum, Clone, Debug, Copy, PartialEq)]
+pub enum EventsFormat {
+    Plain, // One line per minute
+    Json,  // One JSON event per line
+}
+
 /// Defines the time format for the status command
 #[derive(clap::ValueEnum, Clone, Default, Debug, Serialize, Copy)]
 #[serde(rename_all = "lowercase")]
@@ -0,0 +1,464 @@
+use crate::error::*;
+use crate::events::EventKind;
+use crate::focus_mode::FocusPreset;
+use crate::progress_bar::{BarField, FillDirection, GlyphSet};
+use crate::timer_info::TimerPhase;
+use crate::utils::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+/// Defines the user configuration (which is stored as JSON in the config directory)
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub sound: SoundConfig,
+    pub soundscape: SoundscapeConfig,
+    pub lock: LockConfig,
+    pub strict_break: StrictBreakConfig,
+    pub progress_bar: ProgressBarConfig,
+    pub webhooks: Vec<WebhookConfig>,
+    pub mqtt: MqttConfig,
+    pub focus_mode: FocusModeConfig,
+    pub site_blocking: SiteBlockingConfig,
+    pub process_guard: ProcessGuardConfig,
+    pub tasks: TasksConfig,
+}
+
+/// Defines the alarm sound configuration
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundConfig {
+    /// Playback volume (1.0 is the original volume of the sound)
+    pub volume: f32,
+    /// How many times the sound is played in a row
+    pub repeat: u32,
+    /// Sound played when a work timer finishes
+    pub work_end: Option<String>,
+    /// Sound played when a break timer finishes
+    pub break_end: Option<String>,
+    /// Sound played when the timer is about to finish
+    pub warning: Option<String>,
+    /// How many seconds before the end the warning sound is played (0 disables the warning)
+    pub warning_time: i64,
+    /// Name of the audio output device (see `sound devices`). `None` uses the default output.
+    pub device: Option<String>,
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            repeat: 1,
+            work_end: None,
+            break_end: None,
+            warning: None,
+            warning_time: 0,
+            device: None,
+        }
+    }
+}
+
+/// Defines the background sound which plays while a work timer is running
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundscapeConfig {
+    /// Name of a generated sound (`tick`, `white-noise`, `pink-noise`, `brown-noise`) or a path
+    /// to a sound file which is looped. `None` disables the soundscape.
+    pub sound: Option<String>,
+    /// Playback volume of the soundscape
+    pub volume: f32,
+}
+
+impl Default for SoundscapeConfig {
+    fn default() -> Self {
+        Self {
+            sound: None,
+            volume: 0.3,
+        }
+    }
+}
+
+/// Defines how the screen is locked when a `--lock-screen` timer finishes
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockConfig {
+    /// Command which locks the screen (e.g. `swaylock -f`). `None` uses the platform default.
+    pub command: Option<String>,
+    /// Seconds to wait before locking when a work timer finishes (0 locks immediately)
+    pub grace_time: i64,
+    /// Seconds to wait before locking when a break timer finishes. Defaults to `grace_time`.
+    pub break_grace_time: Option<i64>,
+}
+
+impl LockConfig {
+    /// Return the grace period for the given phase in seconds
+    pub fn grace_time_for(&self, phase: TimerPhase) -> i64 {
+        match phase {
+            TimerPhase::Work => self.grace_time,
+            TimerPhase::Break => self.break_grace_time.unwrap_or(self.grace_time),
+        }
+    }
+}
+
+/// Defines the strict break mode which starts a break and keeps the screen locked when a work
+/// timer finishes
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrictBreakConfig {
+    pub enabled: bool,
+    /// Duration of the break in seconds
+    pub duration: i64,
+    /// How often (in seconds) the screen is locked again if it was unlocked during the break
+    pub relock_interval: i64,
+    /// How many times per day a break can be skipped with `pomodoro-cli skip`
+    pub skips_per_day: u32,
+}
+
+impl Default for StrictBreakConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration: 5 * 60,
+            relock_interval: 10,
+            skips_per_day: 1,
+        }
+    }
+}
+
+/// Defines how the progress bar of the wait view is drawn
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressBarConfig {
+    /// Number of cells of the bar (0 fits the bar to the terminal width)
+    pub width: usize,
+    pub glyphs: GlyphSet,
+    pub fill: FillDirection,
+    pub colors: ProgressColors,
+    /// Text fields shown after the bar
+    pub fields: Vec<BarField>,
+}
+
+impl Default for ProgressBarConfig {
+    fn default() -> Self {
+        Self {
+            width: 25,
+            glyphs: GlyphSet::default(),
+            fill: FillDirection::default(),
+            colors: ProgressColors::default(),
+            fields: vec![BarField::Status],
+        }
+    }
+}
+
+/// Defines the colours of the progress bar (names like `dark_green`, `#rrggbb` or ANSI colour
+/// numbers). The state colours take precedence over the phase colours.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressColors {
+    pub work: Option<String>,
+    #[serde(rename = "break")]
+    pub break_time: Option<String>,
+    pub paused: Option<String>,
+    pub finished: Option<String>,
+}
+
+/// Defines an HTTP endpoint which receives the timer events as POST requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Events which are sent to the webhook
+    pub events: Vec<EventKind>,
+    /// Template of the request body with `{{name}}` placeholders. `None` sends the event JSON.
+    pub body: Option<String>,
+    pub content_type: String,
+    pub headers: BTreeMap<String, String>,
+    /// How many times a failed request is retried
+    pub retries: u32,
+    /// Timeout of a single request in seconds
+    pub timeout: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            events: vec![
+                EventKind::Started,
+                EventKind::Paused,
+                EventKind::Finished,
+                EventKind::Stopped,
+            ],
+            body: None,
+            content_type: "application/json".to_string(),
+            headers: BTreeMap::new(),
+            retries: 3,
+            timeout: 5,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Return true if the event is sent to the webhook
+    pub fn accepts(&self, event: EventKind) -> bool {
+        !self.url.is_empty() && self.events.contains(&event)
+    }
+}
+
+/// Defines the MQTT broker and the topics used by the `mqtt` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Topic of the retained timer state
+    pub topic: String,
+    /// Topic which receives the `start`, `pause` and `stop` commands
+    pub command_topic: String,
+    /// How often (in seconds) the state is published while the timer is running
+    pub interval: u64,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 1883,
+            client_id: "pomodoro-cli".to_string(),
+            username: None,
+            password: None,
+            topic: "pomodoro-cli/state".to_string(),
+            command_topic: "pomodoro-cli/command".to_string(),
+            interval: 10,
+        }
+    }
+}
+
+/// Defines how notifications are paused while a work timer is running
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FocusModeConfig {
+    /// Commands of a common notification daemon (`dunst`, `mako`, `swaync`)
+    pub preset: Option<FocusPreset>,
+    /// Command which pauses the notifications (overrides the preset)
+    pub enable_command: Option<String>,
+    /// Command which restores the notifications (overrides the preset)
+    pub disable_command: Option<String>,
+    /// Command which prints the number of notifications (overrides the preset)
+    pub count_command: Option<String>,
+    /// Show how many notifications arrived during the session when it ends
+    pub summarize: bool,
+}
+
+impl Default for FocusModeConfig {
+    fn default() -> Self {
+        Self {
+            preset: None,
+            enable_command: None,
+            disable_command: None,
+            count_command: None,
+            summarize: true,
+        }
+    }
+}
+
+/// Defines the distracting sites which are blocked in the hosts file while a work timer is running
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SiteBlockingConfig {
+    pub enabled: bool,
+    /// Path of the hosts file (the file must be writable by the user)
+    pub hosts_file: String,
+    /// Blocked domains. The `www.` subdomains are blocked as well.
+    pub domains: Vec<String>,
+}
+
+impl Default for SiteBlockingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hosts_file: "/etc/hosts".to_string(),
+            domains: Vec::new(),
+        }
+    }
+}
+
+/// Defines what is done with a distracting process
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GuardPolicy {
+    /// Show a notification
+    #[default]
+    Warn,
+    /// Show a notification and record an interruption
+    Record,
+    /// Record an interruption and pause the process (SIGSTOP) until the work session ends
+    Stop,
+    /// Record an interruption and terminate the process (SIGTERM)
+    Terminate,
+}
+
+/// Defines the distracting processes which are guarded against while a work timer is running
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessGuardConfig {
+    pub enabled: bool,
+    /// Process names (as in `/proc/<pid>/comm`)
+    pub processes: Vec<String>,
+    pub policy: GuardPolicy,
+    /// How often (in seconds) the processes are scanned
+    pub interval: u64,
+}
+
+impl Default for ProcessGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            processes: Vec::new(),
+            policy: GuardPolicy::default(),
+            interval: 5,
+        }
+    }
+}
+
+/// Defines where the tasks of the `task` command are stored
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskSource {
+    /// The task list in the data directory
+    #[default]
+    Local,
+    /// A todo.txt file
+    TodoTxt,
+    /// The `- [ ]` items of a Markdown file
+    Markdown,
+}
+
+/// Defines the task source configuration
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TasksConfig {
+    pub source: TaskSource,
+    /// Path of the todo.txt file
+    pub todo_file: String,
+    /// Path of the Markdown file
+    pub markdown_file: String,
+    /// Marker appended to a Markdown item after each completed pomodoro
+    pub tally_marker: String,
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self {
+            source: TaskSource::default(),
+            todo_file: "~/todo.txt".to_string(),
+            markdown_file: "~/tasks.md".to_string(),
+            tally_marker: "🍅".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Initialize the Config from the JSON file in the config directory. Defaults to default
+    /// values if the file does not exist.
+    pub fn from_file_or_default() -> AppResult<Self> {
+        let path = match get_config_file() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Self::default()),
+        };
+
+        let mut contents = String::new();
+        let mut file = std::fs::File::open(&path)?;
+        file.read_to_string(&mut contents)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            AppError::new(&format!(
+                "Invalid configuration in {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+impl SoundConfig {
+    /// Return the configured sound file for the work-end event
+    pub fn work_end_file(&self) -> Option<PathBuf> {
+        resolve_sound_file(&self.work_end, "work-end").or_else(get_custom_alarm_file)
+    }
+
+    /// Return the configured sound file for the break-end event
+    pub fn break_end_file(&self) -> Option<PathBuf> {
+        resolve_sound_file(&self.break_end, "break-end").or_else(get_custom_alarm_file)
+    }
+
+    /// Return the configured sound file for the warning event
+    pub fn warning_file(&self) -> Option<PathBuf> {
+        resolve_sound_file(&self.warning, "warning")
+    }
+}
+
+/// Return the explicitly configured sound file, or the file with the given name in the config
+/// directory.
+fn resolve_sound_file(configured: &Option<String>, name: &str) -> Option<PathBuf> {
+    match configured {
+        Some(path) => Some(expand_home(path)),
+        None => get_custom_sound_file(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config() {
+        let config: Config = serde_json::from_str(r#"{"sound": {"volume": 0.5}}"#).unwrap();
+        assert_eq!(config.sound.volume, 0.5);
+        assert_eq!(config.sound.repeat, 1);
+        assert_eq!(config.sound.warning_time, 0);
+        assert!(config.sound.work_end.is_none());
+    }
+
+    #[test]
+    fn test_lock_grace_time() {
+        let config: Config = serde_json::from_str(r#"{"lock": {"grace_time": 30}}"#).unwrap();
+        assert_eq!(config.lock.grace_time_for(TimerPhase::Work), 30);
+        assert_eq!(config.lock.grace_time_for(TimerPhase::Break), 30);
+        let config: Config =
+            serde_json::from_str(r#"{"lock": {"grace_time": 30, "break_grace_time": 5}}"#).unwrap();
+        assert_eq!(config.lock.grace_time_for(TimerPhase::Break), 5);
+    }
+
+    #[test]
+    fn test_empty_config() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.sound.volume, 1.0);
+        assert!(config.soundscape.sound.is_none());
+        assert_eq!(config.progress_bar.width, 25);
+        assert_eq!(config.progress_bar.fields, vec![BarField::Status]);
+    }
+
+    #[test]
+    fn test_progress_bar_config() {
+        let config: Config = serde_json::from_str(
+            r#"{"progress_bar": {"glyphs": "braille", "fill": "remaining",
+                "colors": {"break": "green"}, "fields": ["time", "end-time"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.progress_bar.glyphs, GlyphSet::Braille);
+        assert_eq!(config.progress_bar.fill, FillDirection::Remaining);
+        assert_eq!(
+            config.progress_bar.colors.break_time.as_deref(),
+            Some("green")
+        );
+        assert_eq!(
+            config.progress_bar.fields,
+            vec![BarField::Time, BarField::EndTime]
+        );
+    }
+}
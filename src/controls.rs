@@ -0,0 +1,99 @@
+use crate::error::*;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::time::{Duration, Instant};
+
+/// How much time the `+` and `-` keys add or remove (in seconds)
+pub const ADJUST_STEP: i64 = 60;
+
+/// Defines the actions which can be triggered with the keyboard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Control {
+    PauseResume,
+    Stop,
+    AddTime(i64),
+    EditMessage,
+    Detach,
+}
+
+impl Control {
+    /// Map the key event to a control action
+    pub fn from_key(key: &KeyEvent) -> Option<Self> {
+        if key.kind == KeyEventKind::Release {
+            return None;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Self::Detach)
+            }
+            KeyCode::Char('p') | KeyCode::Char(' ') => Some(Self::PauseResume),
+            KeyCode::Char('s') => Some(Self::Stop),
+            KeyCode::Char('+') | KeyCode::Char('=') => Some(Self::AddTime(ADJUST_STEP)),
+            KeyCode::Char('-') => Some(Self::AddTime(-ADJUST_STEP)),
+            KeyCode::Char('m') => Some(Self::EditMessage),
+            KeyCode::Char('q') | KeyCode::Esc => Some(Self::Detach),
+            _ => None,
+        }
+    }
+}
+
+/// Short help text of the key bindings
+pub const CONTROLS_HELP: &str =
+    "[p] pause/resume  [s] stop  [+/-] add/remove 1 min  [m] message  [q] detach";
+
+/// Puts the terminal into raw mode for as long as the guard is alive. Raw mode is disabled when
+/// the guard is dropped, also when returning early with an error.
+pub struct RawModeGuard;
+
+impl RawModeGuard {
+    pub fn new() -> AppResult<Self> {
+        enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Wait for a control key until the timeout expires. Returns `None` if no control key was
+/// pressed in time.
+pub fn wait_for_control(timeout: Duration) -> AppResult<Option<Control>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || !event::poll(remaining)? {
+            return Ok(None);
+        }
+        if let Event::Key(key) = event::read()? {
+            if let Some(control) = Control::from_key(&key) {
+                return Ok(Some(control));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_mapping() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(
+            Control::from_key(&key(KeyCode::Char('p'))),
+            Some(Control::PauseResume)
+        );
+        assert_eq!(
+            Control::from_key(&key(KeyCode::Char('-'))),
+            Some(Control::AddTime(-ADJUST_STEP))
+        );
+        assert_eq!(
+            Control::from_key(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Control::Detach)
+        );
+        assert_eq!(Control::from_key(&key(KeyCode::Char('x'))), None);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::app::*;
+use crate::error::*;
+use crate::events::{follow_events, StateSummary};
+use crate::timer_info::{TimerInfo, TimerPhase};
+use crate::utils::parse_duration;
+use std::thread;
+use std::time::Duration;
+use zbus::blocking::{Connection, ConnectionBuilder};
+use zbus::{dbus_interface, fdo, SignalContext};
+
+/// Well-known name of the service on the session bus
+pub const SERVICE_NAME: &str = "org.pomodorocli.Timer";
+/// Path of the timer object
+pub const OBJECT_PATH: &str = "/org/pomodorocli/Timer";
+
+/// Convert the application error into a D-Bus error reply
+fn failed(error: AppError) -> fdo::Error {
+    fdo::Error::Failed(error.to_string())
+}
+
+/// The timer object which is served on the session bus
+struct TimerService;
+
+#[dbus_interface(name = "org.pomodorocli.Timer")]
+impl TimerService {
+    /// Start a new timer. An empty duration uses the default duration.
+    fn start(&self, duration: &str, message: &str, break_time: bool) -> fdo::Result<()> {
+        start_timer(StartOptions {
+            duration: parse_duration(Some(duration.to_string()).filter(|d| !d.is_empty())),
+            message: message.to_string(),
+            phase: if break_time {
+                TimerPhase::Break
+            } else {
+                TimerPhase::Work
+            },
+            ..Default::default()
+        })
+        .map_err(failed)
+    }
+
+    /// Stop the timer
+    fn stop(&self) -> fdo::Result<()> {
+        stop_timer().map_err(failed)
+    }
+
+    /// Pause the timer, or resume it if it is paused
+    fn pause(&self) -> fdo::Result<()> {
+        pause_timer().map_err(failed)
+    }
+
+    /// Add seconds to the timer (negative values remove time)
+    fn add_time(&self, seconds: i64) -> fdo::Result<()> {
+        add_time(seconds).map_err(failed)
+    }
+
+    /// Summary of the timer as JSON
+    #[dbus_interface(property)]
+    fn status(&self) -> fdo::Result<String> {
+        let timer_info = TimerInfo::from_file_or_default().map_err(failed)?;
+        StateSummary::new(&timer_info).to_json().map_err(failed)
+    }
+
+    /// Emitted on every timer event, with the state (`Running`, `Paused`, `Finished`) and
+    /// the JSON summary
+    #[dbus_interface(signal)]
+    async fn state_changed(ctxt: &SignalContext<'_>, state: &str, status: &str)
+        -> zbus::Result<()>;
+}
+
+/// Emit the StateChanged signal and tell the clients that the Status property has changed.
+fn emit_state_changed(connection: &Connection, timer_info: &TimerInfo) -> AppResult<()> {
+    let status = StateSummary::new(timer_info).to_json()?;
+    let state = format!("{:?}", timer_info.state);
+    let interface = connection
+        .object_server()
+        .interface::<_, TimerService>(OBJECT_PATH)?;
+    let context = interface.signal_context();
+    zbus::block_on(TimerService::state_changed(context, &state, &status))?;
+    zbus::block_on(interface.get().status_changed(context))?;
+    Ok(())
+}
+
+/// Serve the timer object on the bus of the connection builder
+fn serve_timer(builder: ConnectionBuilder) -> AppResult<Connection> {
+    Ok(builder
+        .name(SERVICE_NAME)?
+        .serve_at(OBJECT_PATH, TimerService)?
+        .build()?)
+}
+
+/// Signal every timer event (also from other commands) until the process is terminated.
+/// Malformed event lines are skipped.
+fn signal_events(connection: &Connection) -> AppResult<()> {
+    follow_events(|line| {
+        let timer_info = serde_json::from_str::<serde_json::Value>(line)
+            .and_then(|event| serde_json::from_value::<TimerInfo>(event["timer"].clone()));
+        match timer_info {
+            Ok(timer_info) => {
+                if let Err(e) = emit_state_changed(connection, &timer_info) {
+                    eprintln!("Warning: Cannot signal the event: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Warning: Skipping a malformed event: {}", e),
+        }
+        Ok(true)
+    })
+}
+
+/// Serve the timer on the session bus until the process is terminated. Every timer event (also
+/// from other commands) is signalled.
+pub fn run_dbus_service() -> AppResult<()> {
+    let connection = serve_timer(ConnectionBuilder::session()?)?;
+    println!("Serving {} on the session bus", SERVICE_NAME);
+
+    // Trigger the alarm when the time runs out even if nobody polls the status
+    thread::spawn(|| loop {
+        if let Err(e) = get_status(None, None) {
+            eprintln!("Warning: {}", e);
+        }
+        thread::sleep(Duration::from_secs(1));
+    });
+
+    signal_events(&connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc;
+    use zbus::blocking::{Proxy, ProxyBuilder};
+    use zbus::CacheProperties;
+
+    /// Start a private session bus and return the daemon with its address, or `None` if
+    /// `dbus-daemon` is not installed.
+    fn start_bus() -> Option<(std::process::Child, String)> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--print-address", "--nofork"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some((daemon, address.trim().to_string()))
+    }
+
+    /// Run with `cargo test -- --ignored test_dbus_service`. The test is ignored by default
+    /// because it redirects the timer files through the environment of the test process.
+    #[test]
+    #[ignore = "changes the environment of the test process"]
+    fn test_dbus_service() {
+        let Some((mut daemon, address)) = start_bus() else {
+            eprintln!("Skipping the D-Bus test: dbus-daemon is not available");
+            return;
+        };
+        // Keep the timer files of the test away from the user's files
+        let dir = std::env::temp_dir().join(format!("pomodoro-cli-dbus-{}", std::process::id()));
+        for var in ["XDG_CACHE_HOME", "XDG_DATA_HOME", "XDG_CONFIG_HOME"] {
+            std::env::set_var(var, &dir);
+        }
+        std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let service = serve_timer(ConnectionBuilder::session().unwrap()).unwrap();
+        thread::spawn(move || signal_events(&service));
+        // Let the event follower find the end of the event log
+        thread::sleep(Duration::from_millis(200));
+
+        let client = Connection::session().unwrap();
+        let proxy: Proxy = ProxyBuilder::new_bare(&client)
+            .destination(SERVICE_NAME)
+            .unwrap()
+            .path(OBJECT_PATH)
+            .unwrap()
+            .interface(SERVICE_NAME)
+            .unwrap()
+            .cache_properties(CacheProperties::No)
+            .build()
+            .unwrap();
+        let mut signals = proxy.receive_signal("StateChanged").unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            if let Some(message) = signals.next() {
+                let _ = sender.send(message.body::<(String, String)>());
+            }
+        });
+
+        proxy.call_method("Start", &("1m", "Write", false)).unwrap();
+        let status: String = proxy.get_property("Status").unwrap();
+        let (state, signalled) = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+
+        daemon.kill().unwrap();
+        let _ = daemon.wait();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(status.contains(r#""state":"Running""#), "{}", status);
+        assert!(status.contains(r#""message":"Write""#), "{}", status);
+        assert_eq!(state, "Running");
+        assert!(signalled.contains(r#""duration":60"#), "{}", signalled);
+    }
+}
@@ -1,5 +1,5 @@
 use rodio::decoder::DecoderError;
-use rodio::StreamError;
+use rodio::{PlayError, StreamError};
 use std::io;
 
 pub type AppResult<T> = Result<T, AppError>;
@@ -22,8 +22,11 @@ pub enum AppError {
     Io(io::Error),
     Decoder(DecoderError),
     Stream(StreamError),
+    Play(PlayError),
+    Wav(hound::Error),
     Serde(serde_json::Error),
     Notify(notify_rust::error::Error),
+    DBus(zbus::Error),
     Custom(CustomAppError),
 }
 
@@ -53,6 +56,18 @@ impl From<StreamError> for AppError {
     }
 }
 
+impl From<PlayError> for AppError {
+    fn from(error: PlayError) -> Self {
+        Self::Play(error)
+    }
+}
+
+impl From<hound::Error> for AppError {
+    fn from(error: hound::Error) -> Self {
+        Self::Wav(error)
+    }
+}
+
 impl From<serde_json::Error> for AppError {
     fn from(error: serde_json::Error) -> Self {
         Self::Serde(error)
@@ -65,6 +80,12 @@ impl From<notify_rust::error::Error> for AppError {
     }
 }
 
+impl From<zbus::Error> for AppError {
+    fn from(error: zbus::Error) -> Self {
+        Self::DBus(error)
+    }
+}
+
 impl From<CustomAppError> for AppError {
     fn from(error: CustomAppError) -> Self {
         Self::Custom(error)
@@ -77,8 +98,11 @@ impl std::fmt::Display for AppError {
             Self::Io(error) => write!(f, "IO Error: {}", error),
             Self::Decoder(error) => write!(f, "Decoder Error: {}", error),
             Self::Stream(error) => write!(f, "Stream Error: {}", error),
+            Self::Play(error) => write!(f, "Play Error: {}", error),
+            Self::Wav(error) => write!(f, "WAV Error: {}", error),
             Self::Serde(error) => write!(f, "Serde Error: {}", error),
             Self::Notify(error) => write!(f, "Notify Error: {}", error),
+            Self::DBus(error) => write!(f, "D-Bus Error: {}", error),
             Self::Custom(error) => write!(f, "Error: {}", error),
         }
     }
@@ -0,0 +1,283 @@
+use crate::args::TimeFormat;
+use crate::config::Config;
+use crate::error::*;
+use crate::focus_mode::sync_focus_mode;
+use crate::process_guard::sync_process_guard;
+use crate::site_blocking::sync_site_blocking;
+use crate::timer_info::{TimerInfo, TimerPhase, TimerState};
+use crate::utils::*;
+use crate::webhooks::dispatch_webhooks;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::thread;
+use std::time::Duration;
+
+/// The event log is cleared when it grows beyond this size (in bytes)
+const MAX_EVENT_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Defines the lifecycle events of the timer
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Started,
+    Paused,
+    Resumed,
+    TimeAdded,
+    Warning,
+    Finished,
+    Stopped,
+    PhaseChanged,
+    /// Emitted when an interruption is recorded
+    Interrupted,
+    /// Emitted every second while waiting for the timer
+    Tick,
+    /// Emitted when the waiting is interrupted
+    Detached,
+}
+
+/// Defines an event with the snapshot of the timer (which is emitted as a JSON line)
+#[derive(Debug, Serialize)]
+pub struct TimerEvent<'a> {
+    pub event: EventKind,
+    pub time: i64,
+    pub remaining: i64,
+    pub timer: &'a TimerInfo,
+}
+
+impl<'a> TimerEvent<'a> {
+    pub fn new(event: EventKind, timer: &'a TimerInfo) -> Self {
+        Self {
+            event,
+            time: chrono::Utc::now().timestamp(),
+            remaining: timer.get_time_left().max(0),
+            timer,
+        }
+    }
+
+    /// Return the event as a single JSON line
+    pub fn to_json(&self) -> AppResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Defines the summary of the timer state which is published by the MQTT bridge and the D-Bus
+/// service
+#[derive(Debug, Serialize)]
+pub struct StateSummary<'a> {
+    pub remaining: i64,
+    pub state: TimerState,
+    pub phase: TimerPhase,
+    pub message: &'a str,
+    pub duration: i64,
+    pub text: String,
+}
+
+impl<'a> StateSummary<'a> {
+    pub fn new(timer_info: &'a TimerInfo) -> Self {
+        Self {
+            remaining: timer_info.get_time_left().max(0),
+            state: timer_info.state,
+            phase: timer_info.phase,
+            message: &timer_info.message,
+            duration: timer_info.duration,
+            text: timer_info.get_human_readable(TimeFormat::default()),
+        }
+    }
+
+    /// Return the summary as JSON
+    pub fn to_json(&self) -> AppResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Append the event to the event log which is followed by the `subscribe` command
+fn append_to_event_log(json: &str) -> AppResult<()> {
+    let path = get_event_log_file();
+    let too_large =
+        std::fs::metadata(&path).is_ok_and(|metadata| metadata.len() > MAX_EVENT_LOG_SIZE);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(!too_large)
+        .write(true)
+        .truncate(too_large)
+        .open(path)?;
+    writeln!(file, "{}", json)?;
+    Ok(())
+}
+
+/// Append the event to the event log, update the focus mode, the site blocking and the process
+/// guard, and send the event to the configured webhooks. The new state of the timer is already
+/// stored when the event is published, so failures are only reported as warnings.
+pub fn publish_event(event: EventKind, timer_info: &TimerInfo) {
+    let json = match TimerEvent::new(event, timer_info).to_json() {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("Warning: Cannot serialize the event: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = append_to_event_log(&json) {
+        eprintln!("Warning: Cannot write the event log: {}", e);
+    }
+    let config = match Config::from_file_or_default() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = sync_focus_mode(&config.focus_mode, timer_info) {
+        eprintln!("Warning: {}", e);
+    }
+    if let Err(e) = sync_site_blocking(&config.site_blocking, timer_info) {
+        eprintln!("Warning: {}", e);
+    }
+    if let Err(e) = sync_process_guard(&config.process_guard, timer_info) {
+        eprintln!("Warning: Cannot update the process guard: {}", e);
+    }
+    if let Err(e) = dispatch_webhooks(event, &json) {
+        eprintln!("Warning: Cannot send the webhooks: {}", e);
+    }
+}
+
+/// Follow the event log and pass every new event line to the callback until it returns false.
+/// Only the events published after the call are passed.
+pub fn follow_events(mut on_event: impl FnMut(&str) -> AppResult<bool>) -> AppResult<()> {
+    let path = get_event_log_file();
+    let mut offset = std::fs::metadata(&path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let mut pending = String::new();
+    loop {
+        thread::sleep(Duration::from_millis(100));
+        let Ok(mut file) = std::fs::File::open(&path) else {
+            continue;
+        };
+        let len = file.metadata()?.len();
+        if len < offset {
+            // The log was cleared
+            offset = 0;
+            pending.clear();
+        }
+        if len == offset {
+            continue;
+        }
+        file.seek(SeekFrom::Start(offset))?;
+        let mut contents = String::new();
+        offset += file.read_to_string(&mut contents)? as u64;
+        pending.push_str(&contents);
+
+        // Keep an incomplete last line until the rest of it is written
+        while let Some(end) = pending.find('\n') {
+            let line: String = pending.drain(..=end).collect();
+            if !on_event(line.trim_end())? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Print the events to the standard output as JSON lines. Returns when the output is closed.
+pub fn subscribe() -> AppResult<()> {
+    let mut stdout = std::io::stdout();
+    follow_events(
+        |line| match writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(false),
+            Err(e) => Err(e.into()),
+        },
+    )
+}
+
+/// Serve the events on a Unix domain socket. Every connected client receives the events as
+/// JSON lines.
+#[cfg(unix)]
+pub fn serve_events(path: &std::path::Path) -> AppResult<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::{Arc, Mutex};
+
+    // A socket file left behind by a previous run would make the bind fail
+    if path.exists() && UnixStream::connect(path).is_err() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    println!("Serving events on {}", path.display());
+
+    let clients: Arc<Mutex<Vec<UnixStream>>> = Arc::default();
+    let accepted = clients.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Ok(mut clients) = accepted.lock() {
+                clients.push(stream);
+            }
+        }
+    });
+
+    follow_events(|line| {
+        if let Ok(mut clients) = clients.lock() {
+            clients.retain_mut(|client| writeln!(client, "{}", line).is_ok());
+        }
+        Ok(true)
+    })
+}
+
+#[cfg(not(unix))]
+pub fn serve_events(_path: &std::path::Path) -> AppResult<()> {
+    Err(AppError::new(
+        "Event sockets are only supported on Unix systems.",
+    ))
+}
+
+/// Return the event caused by the state change of the timer, if any
+pub fn state_change_event(previous: TimerState, current: TimerState) -> Option<EventKind> {
+    match (previous, current) {
+        (TimerState::Running, TimerState::Paused) => Some(EventKind::Paused),
+        (TimerState::Paused, TimerState::Running) => Some(EventKind::Resumed),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_change_event() {
+        assert_eq!(
+            state_change_event(TimerState::Running, TimerState::Paused),
+            Some(EventKind::Paused)
+        );
+        assert_eq!(
+            state_change_event(TimerState::Paused, TimerState::Running),
+            Some(EventKind::Resumed)
+        );
+        assert_eq!(
+            state_change_event(TimerState::Running, TimerState::Running),
+            None
+        );
+    }
+
+    #[test]
+    fn test_event_names() -> AppResult<()> {
+        assert_eq!(
+            serde_json::to_string(&EventKind::TimeAdded)?,
+            r#""time_added""#
+        );
+        assert_eq!(
+            serde_json::to_string(&EventKind::PhaseChanged)?,
+            r#""phase_changed""#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_event_json() -> AppResult<()> {
+        let timer_info = TimerInfo::default();
+        let json = TimerEvent::new(EventKind::Tick, &timer_info).to_json()?;
+        assert!(json.starts_with(r#"{"event":"tick","time":"#));
+        assert!(!json.contains('\n'));
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use crate::config::FocusModeConfig;
+use crate::error::*;
+use crate::timer_info::{TimerInfo, TimerPhase};
+use crate::utils::*;
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+/// Defines the built-in commands of the common notification daemons
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FocusPreset {
+    Dunst,
+    Mako,
+    Swaync,
+}
+
+impl FocusPreset {
+    fn enable_command(&self) -> &'static str {
+        match self {
+            FocusPreset::Dunst => "dunstctl set-paused true",
+            FocusPreset::Mako => "makoctl mode -a do-not-disturb",
+            FocusPreset::Swaync => "swaync-client --dnd-on --skip-wait",
+        }
+    }
+
+    fn disable_command(&self) -> &'static str {
+        match self {
+            FocusPreset::Dunst => "dunstctl set-paused false",
+            FocusPreset::Mako => "makoctl mode -r do-not-disturb",
+            FocusPreset::Swaync => "swaync-client --dnd-off --skip-wait",
+        }
+    }
+
+    fn count_command(&self) -> Option<&'static str> {
+        match self {
+            FocusPreset::Dunst => Some("dunstctl count waiting"),
+            FocusPreset::Mako => None,
+            FocusPreset::Swaync => Some("swaync-client --count --skip-wait"),
+        }
+    }
+}
+
+impl FocusModeConfig {
+    /// Return the configured command, or the command of the preset
+    fn command(
+        &self,
+        configured: &Option<String>,
+        preset: fn(&FocusPreset) -> Option<&str>,
+    ) -> Option<String> {
+        configured
+            .clone()
+            .or_else(|| self.preset.as_ref().and_then(preset).map(str::to_string))
+    }
+
+    pub fn enable_command(&self) -> Option<String> {
+        self.command(&self.enable_command, |preset| Some(preset.enable_command()))
+    }
+
+    pub fn disable_command(&self) -> Option<String> {
+        self.command(&self.disable_command, |preset| {
+            Some(preset.disable_command())
+        })
+    }
+
+    pub fn count_command(&self) -> Option<String> {
+        self.command(&self.count_command, FocusPreset::count_command)
+    }
+}
+
+/// Defines the state of the focus mode between the commands (which is stored as JSON in the
+/// system cache directory)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FocusState {
+    active: bool,
+    /// Number of notifications when the focus mode was enabled
+    count_at_start: u64,
+}
+
+impl FocusState {
+    fn from_file_or_default() -> Self {
+        std::fs::read_to_string(get_focus_mode_file())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_to_file(&self) -> AppResult<()> {
+        std::fs::write(get_focus_mode_file(), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Return true if notifications should be paused for the timer
+pub fn is_focus_time(timer_info: &TimerInfo) -> bool {
+    timer_info.is_running() && timer_info.phase == TimerPhase::Work && !timer_info.is_time_run_out()
+}
+
+/// Return the number of notifications reported by the count command
+fn count_notifications(config: &FocusModeConfig) -> Option<u64> {
+    let command = config.count_command()?;
+    shell_command_output(&command).ok()?.trim().parse().ok()
+}
+
+/// Return the summary of the notifications which arrived during the focus session
+pub fn summary_message(count_at_start: u64, count_at_end: u64) -> Option<String> {
+    match count_at_end.saturating_sub(count_at_start) {
+        0 => None,
+        1 => Some("1 notification arrived during the focus session.".to_string()),
+        count => Some(format!(
+            "{} notifications arrived during the focus session.",
+            count
+        )),
+    }
+}
+
+/// Pause the notifications while a work timer is running and restore them otherwise. Only the
+/// notifications paused by the focus mode are restored. Failing commands are reported as
+/// warnings so that they never prevent controlling the timer.
+pub fn sync_focus_mode(config: &FocusModeConfig, timer_info: &TimerInfo) -> AppResult<()> {
+    let mut state = FocusState::from_file_or_default();
+    let focus = is_focus_time(timer_info);
+    if focus == state.active {
+        return Ok(());
+    }
+
+    if focus {
+        let Some(command) = config.enable_command() else {
+            return Ok(());
+        };
+        state.count_at_start = count_notifications(config).unwrap_or(0);
+        if let Err(e) = run_shell_command(&command) {
+            eprintln!("Warning: Cannot enable the focus mode: {}", e);
+        }
+    } else {
+        let count_at_end = count_notifications(config);
+        if let Some(command) = config.disable_command() {
+            if let Err(e) = run_shell_command(&command) {
+                eprintln!("Warning: Cannot disable the focus mode: {}", e);
+            }
+        }
+        let summary = count_at_end.and_then(|count| summary_message(state.count_at_start, count));
+        if let Some(summary) = summary.filter(|_| config.summarize) {
+            if let Err(e) = Notification::new()
+                .summary("Pomodoro Timer")
+                .body(&summary)
+                .appname("pomodoro-cli")
+                .show()
+            {
+                eprintln!("Warning: Cannot show the focus summary: {}", e);
+            }
+        }
+    }
+    state.active = focus;
+    state.write_to_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_commands() {
+        let config = FocusModeConfig {
+            preset: Some(FocusPreset::Dunst),
+            disable_command: Some("my-dnd off".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.enable_command().as_deref(),
+            Some("dunstctl set-paused true")
+        );
+        assert_eq!(config.disable_command().as_deref(), Some("my-dnd off"));
+        assert_eq!(
+            config.count_command().as_deref(),
+            Some("dunstctl count waiting")
+        );
+
+        let config = FocusModeConfig {
+            preset: Some(FocusPreset::Mako),
+            ..Default::default()
+        };
+        assert!(config.count_command().is_none());
+        assert!(FocusModeConfig::default().enable_command().is_none());
+    }
+
+    #[test]
+    fn test_summary_message() {
+        assert_eq!(summary_message(3, 3), None);
+        assert_eq!(summary_message(5, 2), None);
+        assert_eq!(
+            summary_message(0, 1).as_deref(),
+            Some("1 notification arrived during the focus session.")
+        );
+        assert_eq!(
+            summary_message(1, 4).as_deref(),
+            Some("3 notifications arrived during the focus session.")
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use crate::error::*;
+use crate::tasks::record_pomodoro;
+use crate::timer_info::{TimerInfo, TimerPhase};
+use crate::utils::*;
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::prelude::*;
+
+/// Defines a finished timer session (which is stored as a JSON line in the data directory)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub phase: TimerPhase,
+    pub message: String,
+    /// True if the time ran out, false if the timer was stopped early
+    pub completed: bool,
+    /// ID of the task which the session was linked to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<u32>,
+    #[serde(default)]
+    pub internal_interruptions: u32,
+    #[serde(default)]
+    pub external_interruptions: u32,
+    /// True if the work session was abandoned after an interruption
+    #[serde(default)]
+    pub void: bool,
+}
+
+impl HistoryEntry {
+    /// Create a history entry of the timer. A completed timer ended when the time ran out, and
+    /// a timer stopped early ends now.
+    pub fn from_timer_info(timer_info: &TimerInfo, completed: bool) -> Self {
+        let end_time = if completed {
+            timer_info.start_time + timer_info.duration
+        } else {
+            chrono::Utc::now().timestamp()
+        };
+        let (internal_interruptions, external_interruptions) = timer_info.interruption_counts();
+        Self {
+            start_time: timer_info.session_start_time(),
+            end_time,
+            phase: timer_info.phase,
+            message: timer_info.message.clone(),
+            completed,
+            task_id: timer_info.task.as_ref().map(|task| task.id),
+            internal_interruptions,
+            external_interruptions,
+            void: !completed && !timer_info.interruptions.is_empty(),
+        }
+    }
+
+    /// Return the local date when the session ended
+    pub fn date(&self) -> Option<NaiveDate> {
+        Local
+            .timestamp_opt(self.end_time, 0)
+            .single()
+            .map(|time| time.date_naive())
+    }
+
+    /// Return the length of the session in seconds
+    pub fn length(&self) -> i64 {
+        (self.end_time - self.start_time).max(0)
+    }
+
+    /// Return true if this is a completed work session (i.e. a pomodoro)
+    pub fn is_pomodoro(&self) -> bool {
+        self.completed && self.phase == TimerPhase::Work
+    }
+}
+
+/// Append the entry to the history file.
+pub fn append_history(entry: &HistoryEntry) -> AppResult<()> {
+    let path = get_history_file();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Record the end of the timer in the history. Timers which are already finished (and the
+/// default timer before any timer has been started) are ignored. A completed work session is
+/// counted for the linked task.
+pub fn record_timer_end(timer_info: &mut TimerInfo) -> AppResult<()> {
+    if !TimerInfo::info_file_exists() || (!timer_info.is_running() && !timer_info.is_paused()) {
+        return Ok(());
+    }
+    let completed = timer_info.is_running() && timer_info.is_time_run_out();
+    append_history(&HistoryEntry::from_timer_info(timer_info, completed))?;
+    if completed && timer_info.phase == TimerPhase::Work {
+        record_pomodoro(timer_info)?;
+    }
+    Ok(())
+}
+
+/// Read all the entries of the history file (oldest first). Invalid lines are skipped.
+pub fn read_history() -> AppResult<Vec<HistoryEntry>> {
+    let path = get_history_file();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut contents = String::new();
+    std::fs::File::open(path)?.read_to_string(&mut contents)?;
+    Ok(parse_history(&contents))
+}
+
+/// Parse the JSON lines of the history file
+pub fn parse_history(contents: &str) -> Vec<HistoryEntry> {
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Return the number of completed pomodoros and the total focus time (in seconds) on the date
+pub fn pomodoros_on(entries: &[HistoryEntry], date: NaiveDate) -> (usize, i64) {
+    entries
+        .iter()
+        .filter(|entry| entry.is_pomodoro() && entry.date() == Some(date))
+        .fold((0, 0), |(count, time), entry| {
+            (count + 1, time + entry.length())
+        })
+}
+
+/// Return the number of internal and external interruptions of the work sessions on the date
+pub fn interruptions_on(entries: &[HistoryEntry], date: NaiveDate) -> (u32, u32) {
+    entries
+        .iter()
+        .filter(|entry| entry.date() == Some(date))
+        .fold((0, 0), |(internal, external), entry| {
+            (
+                internal + entry.internal_interruptions,
+                external + entry.external_interruptions,
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(end_time: i64, phase: TimerPhase, completed: bool) -> HistoryEntry {
+        HistoryEntry {
+            start_time: end_time - 1500,
+            end_time,
+            phase,
+            message: "".to_string(),
+            completed,
+            task_id: None,
+            internal_interruptions: 0,
+            external_interruptions: 0,
+            void: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let line = serde_json::to_string(&entry(1_700_000_000, TimerPhase::Work, true)).unwrap();
+        let contents = format!("{}\nnot json\n{}\n", line, line);
+        assert_eq!(parse_history(&contents).len(), 2);
+    }
+
+    #[test]
+    fn test_pomodoros_on() {
+        let now = chrono::Utc::now().timestamp();
+        let entries = vec![
+            entry(now, TimerPhase::Work, true),
+            entry(now, TimerPhase::Work, false),
+            entry(now, TimerPhase::Break, true),
+            entry(now - 3 * 86400, TimerPhase::Work, true),
+        ];
+        let today = Local::now().date_naive();
+        assert_eq!(pomodoros_on(&entries, today), (1, 1500));
+    }
+
+    #[test]
+    fn test_void_session() {
+        let mut timer_info = TimerInfo {
+            state: crate::timer_info::TimerState::Running,
+            start_time: chrono::Utc::now().timestamp(),
+            ..Default::default()
+        };
+        assert!(!HistoryEntry::from_timer_info(&timer_info, false).void);
+
+        timer_info
+            .interruptions
+            .push(crate::timer_info::Interruption {
+                time: timer_info.start_time,
+                kind: crate::timer_info::InterruptionKind::External,
+                note: "Phone call".to_string(),
+            });
+        let abandoned = HistoryEntry::from_timer_info(&timer_info, false);
+        assert!(abandoned.void);
+        assert_eq!(abandoned.external_interruptions, 1);
+        assert!(!HistoryEntry::from_timer_info(&timer_info, true).void);
+
+        let today = Local::now().date_naive();
+        assert_eq!(interruptions_on(&[abandoned], today), (0, 1));
+    }
+}
@@ -1,8 +1,31 @@
 mod app;
 mod args;
+mod config;
+mod controls;
+mod dbus;
 mod error;
+mod events;
+mod focus_mode;
+mod history;
+mod markdown_tasks;
+mod mqtt;
+mod process_guard;
+mod progress_bar;
+mod screen_lock;
+mod server;
+mod site_blocking;
+mod sound;
+mod soundscape;
+mod strict_break;
+mod synth;
+mod task_file;
+mod tasks;
+mod team;
 mod timer_info;
+mod todo_txt;
+mod tui;
 mod utils;
+mod webhooks;
 
 use crate::args::Cli;
 use clap::Parser;
@@ -10,8 +33,11 @@ use clap::Parser;
 /// Pomodoro timer is a simple timer that helps you to stay focused on your task.
 fn main() {
     let args = Cli::parse();
-    if let Err(e) = app::run(&args) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    match app::run(&args) {
+        Ok(exit_code) => std::process::exit(exit_code),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
     }
 }
@@ -0,0 +1,251 @@
+use crate::config::TaskSource;
+use crate::error::*;
+use crate::task_file::{line_content, FileTask, LineFile, TaskFile};
+use crate::timer_info::TimerTask;
+use std::path::Path;
+
+/// Defines a checklist item (`- [ ] ...`) of a Markdown file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecklistItem {
+    /// Line number (starting from 1), which is used as the ID of the task
+    pub line: u32,
+    pub checked: bool,
+    /// Text of the item without the tally markers
+    pub title: String,
+    /// Number of tally markers (i.e. completed pomodoros)
+    pub tally: u32,
+    /// Byte offset of the `[` of the checkbox
+    checkbox: usize,
+}
+
+impl FileTask for ChecklistItem {
+    fn line(&self) -> u32 {
+        self.line
+    }
+
+    fn is_done(&self) -> bool {
+        self.checked
+    }
+
+    fn snapshot(&self) -> TimerTask {
+        TimerTask {
+            id: self.line,
+            title: self.title.clone(),
+            completed: self.tally,
+            estimate: 0,
+            source: TaskSource::Markdown,
+        }
+    }
+}
+
+/// Return the byte offset of the checkbox and whether it is checked, or `None` if the line is
+/// not a checklist item. Bullet (`-`, `*`, `+`) and numbered (`1.`, `1)`) items are supported.
+fn parse_checkbox(line: &str) -> Option<(usize, bool)> {
+    let content = line_content(line);
+    let rest = content.trim_start();
+    let indent = content.len() - rest.len();
+    let marker = if rest.starts_with("- ") || rest.starts_with("* ") || rest.starts_with("+ ") {
+        2
+    } else {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let after = rest.get(digits..)?;
+        if digits == 0 || !(after.starts_with(". ") || after.starts_with(") ")) {
+            return None;
+        }
+        digits + 2
+    };
+    let offset = indent + marker;
+    let checked = match content.get(offset..offset + 3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let after = &content[offset + 3..];
+    if !after.is_empty() && !after.starts_with(' ') {
+        return None;
+    }
+    Some((offset, checked))
+}
+
+/// Defines a Markdown file with checklist items
+#[derive(Debug)]
+pub struct MarkdownFile {
+    file: LineFile,
+    tally_marker: String,
+}
+
+impl MarkdownFile {
+    /// Read the Markdown file. A missing file has no items.
+    pub fn open(path: &Path, tally_marker: &str) -> AppResult<Self> {
+        Ok(Self {
+            file: LineFile::open(path)?,
+            tally_marker: tally_marker.to_string(),
+        })
+    }
+
+    /// Append a tally marker to the item
+    pub fn add_tally(&mut self, item: &ChecklistItem) {
+        let Some(content) = self.file.line(item.line) else {
+            return;
+        };
+        let text = content.trim_end();
+        // Trailing whitespace (e.g. a hard line break) is kept after the marker
+        let trailing = &content[text.len()..];
+        let separator = if text.ends_with(&self.tally_marker) {
+            ""
+        } else {
+            " "
+        };
+        let updated = format!("{}{}{}{}", text, separator, self.tally_marker, trailing);
+        self.file.update(item.line, &updated);
+    }
+}
+
+impl TaskFile for MarkdownFile {
+    type Item = ChecklistItem;
+
+    /// Return the checklist items (items in fenced code blocks are skipped)
+    fn items(&self) -> Vec<ChecklistItem> {
+        let mut in_code = false;
+        let mut items = Vec::new();
+        for (line, content) in self.file.lines() {
+            let trimmed = content.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code = !in_code;
+                continue;
+            }
+            let Some((checkbox, checked)) = parse_checkbox(content).filter(|_| !in_code) else {
+                continue;
+            };
+            let text = &content[checkbox + 3..];
+            let (title, tally) = if self.tally_marker.is_empty() {
+                (text.trim().to_string(), 0)
+            } else {
+                (
+                    text.replace(&self.tally_marker, "").trim().to_string(),
+                    text.matches(&self.tally_marker).count() as u32,
+                )
+            };
+            if title.is_empty() {
+                continue;
+            }
+            items.push(ChecklistItem {
+                line,
+                checked,
+                title,
+                tally,
+                checkbox,
+            });
+        }
+        items
+    }
+
+    /// Append an unchecked item to the end of the file
+    fn add(&mut self, title: &str, estimate: u32) -> AppResult<u32> {
+        if estimate > 0 {
+            return Err(estimates_not_supported());
+        }
+        Ok(self.file.append(&format!("- [ ] {}", title)))
+    }
+
+    fn set_estimate(&mut self, _line: u32, _estimate: u32) -> AppResult<()> {
+        Err(estimates_not_supported())
+    }
+
+    /// Tick the checkbox of the item
+    fn complete(&mut self, item: &ChecklistItem) {
+        let Some(content) = self.file.line(item.line) else {
+            return;
+        };
+        let mut content = content.to_string();
+        content.replace_range(item.checkbox + 1..item.checkbox + 2, "x");
+        self.file.update(item.line, &content);
+    }
+
+    fn save(&self) -> AppResult<()> {
+        self.file.save()
+    }
+}
+
+fn estimates_not_supported() -> AppError {
+    AppError::new("Estimates are not supported for Markdown task lists.")
+}
+
+/// Append a tally marker to the item of the task. Returns the updated snapshot, or `None` if the
+/// item is no longer in the file.
+pub fn record_pomodoro(
+    path: &Path,
+    tally_marker: &str,
+    task: &TimerTask,
+) -> AppResult<Option<TimerTask>> {
+    let mut file = MarkdownFile::open(path, tally_marker)?;
+    let Some(item) = file.find(task) else {
+        return Ok(None);
+    };
+    file.add_tally(&item);
+    file.save()?;
+    Ok(Some(file.get(item.line)?.snapshot()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = "# Monday\r\n\r\n- [ ] Write RFC 🍅\r\n  * [x] Review\r\n\
+        1. [ ] Fix build\r\n- [link](url)\r\n```\r\n- [ ] Not a task\r\n```\r\nNotes";
+
+    fn markdown(contents: &str) -> MarkdownFile {
+        MarkdownFile {
+            file: LineFile::parse(Path::new("plan.md"), contents),
+            tally_marker: "🍅".to_string(),
+        }
+    }
+
+    fn plan() -> MarkdownFile {
+        markdown(PLAN)
+    }
+
+    #[test]
+    fn test_items() {
+        let items = plan().items();
+        let titles = items
+            .iter()
+            .map(|item| item.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["Write RFC", "Review", "Fix build"]);
+        assert_eq!(items[0].line, 3);
+        assert_eq!(items[0].tally, 1);
+        assert!(items[1].checked);
+    }
+
+    #[test]
+    fn test_update_keeps_other_content() {
+        let mut file = plan();
+        let item = file.get(3).unwrap();
+        file.add_tally(&item);
+        let item = file.get(5).unwrap();
+        file.add_tally(&item);
+        file.complete(&item);
+        let expected = PLAN
+            .replace("Write RFC 🍅", "Write RFC 🍅🍅")
+            .replace("1. [ ] Fix build", "1. [x] Fix build 🍅");
+        assert_eq!(file.file.contents(), expected);
+        assert_eq!(file.get(3).unwrap().tally, 2);
+    }
+
+    #[test]
+    fn test_tally_keeps_trailing_spaces() {
+        let mut file = markdown("- [ ] Write RFC  \nNotes");
+        let item = file.get(1).unwrap();
+        file.add_tally(&item);
+        assert_eq!(file.file.contents(), "- [ ] Write RFC 🍅  \nNotes");
+    }
+
+    #[test]
+    fn test_append() {
+        let mut file = plan();
+        assert_eq!(file.add("Deploy", 0).unwrap(), 11);
+        assert!(file.add("Deploy", 2).is_err());
+        assert!(file.file.contents().ends_with("Notes\r\n- [ ] Deploy\r\n"));
+    }
+}
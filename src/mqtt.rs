@@ -0,0 +1,190 @@
+use crate::app::*;
+use crate::config::{Config, MqttConfig};
+use crate::error::*;
+use crate::events::StateSummary;
+use crate::server::StartRequest;
+use crate::timer_info::TimerInfo;
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Defines the commands which are accepted on the command topic
+#[derive(Debug)]
+pub enum MqttCommand {
+    Start(StartRequest),
+    Pause,
+    Stop,
+}
+
+impl MqttCommand {
+    /// Parse the command from a plain word (`start`, `pause`, `stop`) or from a JSON object
+    /// with the `command` and the options of the `start` command (e.g.
+    /// `{"command": "start", "duration": "25m"}`).
+    pub fn parse(payload: &str) -> AppResult<Self> {
+        let payload = payload.trim();
+        let (command, options) = if payload.starts_with('{') {
+            let value: serde_json::Value = serde_json::from_str(payload)?;
+            let command = value["command"].as_str().unwrap_or_default().to_string();
+            (command, Some(value))
+        } else {
+            (payload.to_string(), None)
+        };
+        match command.to_lowercase().as_str() {
+            "start" => Ok(Self::Start(match options {
+                Some(options) => serde_json::from_value(options)?,
+                None => StartRequest::default(),
+            })),
+            "pause" => Ok(Self::Pause),
+            "stop" => Ok(Self::Stop),
+            _ => Err(AppError::new(&format!("Unknown command `{}`", command))),
+        }
+    }
+
+    fn execute(self) -> AppResult<()> {
+        match self {
+            Self::Start(request) => start_timer(request.into()),
+            Self::Pause => pause_timer(),
+            Self::Stop => stop_timer(),
+        }
+    }
+}
+
+/// Return the topic which tells whether the bridge is connected
+fn availability_topic(config: &MqttConfig) -> String {
+    format!("{}/availability", config.topic)
+}
+
+/// Publish the state whenever the timer changes and every `interval` seconds while it is
+/// running. Errors are reported as warnings so that the bridge keeps running.
+fn publish_state_loop(client: Client, config: MqttConfig) {
+    let interval = Duration::from_secs(config.interval.max(1));
+    let mut last_timer: Option<TimerInfo> = None;
+    let mut last_publish = Instant::now();
+    loop {
+        thread::sleep(Duration::from_millis(250));
+        // Also triggers the alarm when the time runs out
+        if let Err(e) = get_status(None, None) {
+            eprintln!("Warning: {}", e);
+        }
+        let timer_info = match TimerInfo::from_file_or_default() {
+            Ok(timer_info) => timer_info,
+            Err(e) => {
+                eprintln!("Warning: Cannot read the timer: {}", e);
+                continue;
+            }
+        };
+        let changed = last_timer.as_ref() != Some(&timer_info);
+        let due = timer_info.is_running() && last_publish.elapsed() >= interval;
+        if changed || due {
+            let published = StateSummary::new(&timer_info)
+                .to_json()
+                .and_then(|payload| {
+                    client
+                        .publish(&config.topic, QoS::AtLeastOnce, true, payload)
+                        .map_err(|e| AppError::new(&e.to_string()))
+                });
+            if let Err(e) = published {
+                eprintln!("Warning: Cannot publish the timer state: {}", e);
+            }
+            last_timer = Some(timer_info);
+            last_publish = Instant::now();
+        }
+    }
+}
+
+/// Subscribe to the command topic and mark the bridge online. The subscription does not survive
+/// a reconnect with a clean session, so this is done on every connect. The requests may block
+/// until the event loop has room for them, which is why they are not sent from the event loop.
+fn announce_online(client: &Client, config: &MqttConfig) {
+    if let Err(e) = client.subscribe(&config.command_topic, QoS::AtLeastOnce) {
+        eprintln!("Warning: Cannot subscribe to the MQTT command topic: {}", e);
+    }
+    if let Err(e) = client.publish(availability_topic(config), QoS::AtLeastOnce, true, "online") {
+        eprintln!("Warning: Cannot publish the MQTT availability: {}", e);
+    }
+}
+
+/// Connect to the MQTT broker, publish the timer state and execute the commands received on the
+/// command topic until the process is terminated.
+pub fn run_mqtt_bridge() -> AppResult<()> {
+    let config = Config::from_file_or_default()?.mqtt;
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        availability_topic(&config),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+
+    let (client, mut connection) = Client::new(options, 16);
+    let publisher = client.clone();
+    let publisher_config = config.clone();
+    thread::spawn(move || publish_state_loop(publisher, publisher_config));
+
+    println!(
+        "Connecting to the MQTT broker {}:{}",
+        config.host, config.port
+    );
+    for notification in connection.iter() {
+        match notification {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                println!("Connected to the MQTT broker.");
+                let client = client.clone();
+                let config = config.clone();
+                thread::spawn(move || announce_online(&client, &config));
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let payload = String::from_utf8_lossy(&publish.payload);
+                if let Err(e) = MqttCommand::parse(&payload).and_then(MqttCommand::execute) {
+                    eprintln!("Warning: Cannot execute the MQTT command: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Warning: MQTT connection error: {}. Reconnecting...", e);
+                thread::sleep(Duration::from_secs(5));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert!(matches!(
+            MqttCommand::parse(" pause\n"),
+            Ok(MqttCommand::Pause)
+        ));
+        assert!(matches!(MqttCommand::parse("STOP"), Ok(MqttCommand::Stop)));
+        assert!(MqttCommand::parse("jump").is_err());
+
+        let command =
+            MqttCommand::parse(r#"{"command": "start", "duration": "50m", "break": true}"#);
+        let Ok(MqttCommand::Start(request)) = command else {
+            panic!("expected a start command");
+        };
+        assert_eq!(request.duration.as_deref(), Some("50m"));
+        assert!(request.break_time);
+    }
+
+    #[test]
+    fn test_state_payload() -> AppResult<()> {
+        let timer_info = TimerInfo {
+            message: "Write".to_string(),
+            ..Default::default()
+        };
+        let payload = StateSummary::new(&timer_info).to_json()?;
+        assert!(payload.contains(r#""state":"Paused""#));
+        assert!(payload.contains(r#""phase":"Work""#));
+        assert!(payload.contains(r#""message":"Write""#));
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use crate::config::{Config, GuardPolicy, ProcessGuardConfig};
+use crate::error::*;
+use crate::events::{publish_event, EventKind};
+use crate::focus_mode::is_focus_time;
+use crate::timer_info::{InterruptionKind, TimerInfo};
+use crate::utils::*;
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Length of the process names in `/proc/<pid>/comm` (without the terminating null)
+const COMM_LENGTH: usize = 15;
+
+/// Defines a running process
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuardedProcess {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Return true if the process name matches the configured name. The kernel truncates the names
+/// to 15 characters.
+pub fn matches_name(process_name: &str, configured: &str) -> bool {
+    let configured: String = configured.chars().take(COMM_LENGTH).collect();
+    process_name.eq_ignore_ascii_case(&configured)
+}
+
+/// Return the name of the process or `None` if the process does not exist
+fn process_name(pid: u32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .ok()
+        .map(|name| name.trim_end().to_string())
+}
+
+/// Return the running processes which match any of the names
+pub fn find_processes(names: &[String]) -> Vec<GuardedProcess> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| {
+            let name = process_name(pid)?;
+            names
+                .iter()
+                .any(|configured| matches_name(&name, configured))
+                .then_some(GuardedProcess { pid, name })
+        })
+        .collect()
+}
+
+/// Send the signal (e.g. `STOP`) to the process
+fn send_signal(process: &GuardedProcess, signal: &str) -> AppResult<()> {
+    let status = Command::new("kill")
+        .args([format!("-{}", signal), process.pid.to_string()])
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(AppError::new(&format!(
+            "Cannot send SIG{} to {} ({})",
+            signal, process.name, process.pid
+        )));
+    }
+    Ok(())
+}
+
+fn read_stopped_processes() -> Vec<GuardedProcess> {
+    std::fs::read_to_string(get_stopped_processes_file())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn write_stopped_processes(processes: &[GuardedProcess]) -> AppResult<()> {
+    std::fs::write(
+        get_stopped_processes_file(),
+        serde_json::to_string(processes)?,
+    )?;
+    Ok(())
+}
+
+/// Resume the processes stopped by the guard. Processes which have exited (or whose PID has been
+/// reused) are skipped.
+pub fn resume_stopped_processes() -> AppResult<()> {
+    let processes = read_stopped_processes();
+    if processes.is_empty() {
+        return Ok(());
+    }
+    for process in &processes {
+        if process_name(process.pid).as_deref() == Some(process.name.as_str()) {
+            if let Err(e) = send_signal(process, "CONT") {
+                eprintln!("Warning: {}", e);
+            }
+        }
+    }
+    std::fs::remove_file(get_stopped_processes_file())?;
+    Ok(())
+}
+
+/// Return true if the process guard of the previous call is still running
+fn is_guard_running() -> bool {
+    std::fs::read_to_string(get_process_guard_file())
+        .ok()
+        .and_then(|pid| pid.trim().parse::<u32>().ok())
+        .and_then(process_name)
+        .is_some_and(|name| matches_name(&name, "pomodoro-cli"))
+}
+
+/// Start the process guard while a work timer is running, and resume the stopped processes
+/// otherwise.
+pub fn sync_process_guard(config: &ProcessGuardConfig, timer_info: &TimerInfo) -> AppResult<()> {
+    if !is_focus_time(timer_info) {
+        return resume_stopped_processes();
+    }
+    if !config.enabled || config.processes.is_empty() || is_guard_running() {
+        return Ok(());
+    }
+    let child = Command::new(std::env::current_exe()?)
+        .arg("guard-processes")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()?;
+    std::fs::write(get_process_guard_file(), child.id().to_string())?;
+    Ok(())
+}
+
+/// Apply the policy to the distracting process
+fn enforce(process: &GuardedProcess, policy: GuardPolicy) -> AppResult<()> {
+    let action = match policy {
+        GuardPolicy::Warn => "is running",
+        GuardPolicy::Record => "was started",
+        GuardPolicy::Stop => "was paused",
+        GuardPolicy::Terminate => "was closed",
+    };
+    let body = format!("{} {} during the focus session.", process.name, action);
+    if let Err(e) = Notification::new()
+        .summary("Pomodoro Timer")
+        .body(&body)
+        .appname("pomodoro-cli")
+        .show()
+    {
+        eprintln!("Warning: Cannot show the notification: {}", e);
+    }
+
+    match policy {
+        GuardPolicy::Stop => {
+            send_signal(process, "STOP")?;
+            let mut stopped = read_stopped_processes();
+            stopped.push(process.clone());
+            write_stopped_processes(&stopped)?;
+        }
+        GuardPolicy::Terminate => send_signal(process, "TERM")?,
+        GuardPolicy::Warn | GuardPolicy::Record => {}
+    }
+    // The signal is applied even if the interruption cannot be recorded
+    if policy != GuardPolicy::Warn {
+        let note = format!("{} started", process.name);
+        match TimerInfo::record_interruption(InterruptionKind::Internal, note) {
+            Ok(timer_info) => publish_event(EventKind::Interrupted, &timer_info),
+            Err(e) => eprintln!("Warning: Cannot record the interruption: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Scan the processes until the work timer is no longer running. Every distracting process is
+/// handled once per session. The stopped processes are resumed when the guard exits, because
+/// a timer which simply runs out does not publish an event.
+pub fn guard_processes() -> AppResult<()> {
+    let config = Config::from_file_or_default()?.process_guard;
+    let mut handled = HashSet::new();
+    loop {
+        let timer_info = TimerInfo::from_file_or_default()?;
+        if !config.enabled || !is_focus_time(&timer_info) {
+            std::fs::remove_file(get_process_guard_file()).ok();
+            return resume_stopped_processes();
+        }
+        for process in find_processes(&config.processes) {
+            if handled.insert(process.pid) {
+                if let Err(e) = enforce(&process, config.policy) {
+                    eprintln!("Warning: {}", e);
+                }
+            }
+        }
+        thread::sleep(Duration::from_secs(config.interval.max(1)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_name() {
+        assert!(matches_name("discord", "Discord"));
+        assert!(matches_name("steamwebhelper", "steamwebhelper"));
+        assert!(matches_name("signal-desktop-", "signal-desktop-beta"));
+        assert!(!matches_name("discord", "disc"));
+    }
+
+    #[test]
+    fn test_find_processes() {
+        // The test binary itself is always running
+        let name = process_name(std::process::id());
+        if let Some(name) = name {
+            let processes = find_processes(&[name]);
+            assert!(processes
+                .iter()
+                .any(|process| process.pid == std::process::id()));
+        }
+        assert!(find_processes(&[]).is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+use crate::args::TimeFormat;
+use crate::config::ProgressBarConfig;
+use crate::timer_info::{TimerInfo, TimerPhase, TimerState};
+use crossterm::style::{Color, Stylize};
+use serde::{Deserialize, Serialize};
+
+/// Smallest bar width used when the bar is fitted to the terminal
+const MIN_AUTO_WIDTH: usize = 10;
+
+/// Defines the characters used to draw the bar
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GlyphSet {
+    /// `|####-----|`
+    #[default]
+    Ascii,
+    /// `▕███▌░░░░░▏` with eighth-cell precision
+    Blocks,
+    /// `⣿⣿⣿⡆⣀⣀⣀⣀` with eighth-cell precision
+    Braille,
+}
+
+impl GlyphSet {
+    /// Return the left and right border of the bar
+    fn borders(&self) -> (&'static str, &'static str) {
+        match self {
+            GlyphSet::Ascii => ("|", "|"),
+            GlyphSet::Blocks => ("▕", "▏"),
+            GlyphSet::Braille => ("", ""),
+        }
+    }
+
+    /// Return the full and the empty cell
+    fn cells(&self) -> (char, char) {
+        match self {
+            GlyphSet::Ascii => ('#', '-'),
+            GlyphSet::Blocks => ('█', '░'),
+            GlyphSet::Braille => ('⣿', '⣀'),
+        }
+    }
+
+    /// Return the partially filled cells from 1/8 to 7/8 (empty if not supported)
+    fn partials(&self) -> &'static [char] {
+        match self {
+            GlyphSet::Ascii => &[],
+            GlyphSet::Blocks => &['▏', '▎', '▍', '▌', '▋', '▊', '▉'],
+            GlyphSet::Braille => &['⡀', '⡄', '⡆', '⡇', '⣇', '⣧', '⣷'],
+        }
+    }
+}
+
+/// Defines what the filled part of the bar shows
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FillDirection {
+    /// The bar fills up as time passes
+    #[default]
+    Elapsed,
+    /// The bar empties as time passes
+    Remaining,
+}
+
+/// Defines the text fields shown after the bar
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BarField {
+    /// Time left with the message or state (same as `status`)
+    Status,
+    /// Time left
+    Time,
+    /// Local time when the timer finishes
+    EndTime,
+    /// Message of the timer
+    Message,
+    /// Elapsed or remaining percentage (depending on the fill direction)
+    Percentage,
+}
+
+/// Return the fraction (0.0 to 1.0) of the bar to fill. A timer without duration is full when
+/// showing the elapsed time and empty when showing the remaining time.
+pub fn fill_fraction(timer_info: &TimerInfo, direction: FillDirection) -> f64 {
+    let elapsed = if timer_info.duration > 0 {
+        (timer_info.get_time_elapsed() as f64 / timer_info.duration as f64).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    match direction {
+        FillDirection::Elapsed => elapsed,
+        FillDirection::Remaining => 1.0 - elapsed,
+    }
+}
+
+/// Render the cells of the bar (without borders) with the given width
+pub fn render_cells(fraction: f64, width: usize, glyphs: GlyphSet) -> String {
+    let (full, empty) = glyphs.cells();
+    let partials = glyphs.partials();
+    let steps = partials.len() + 1;
+    let filled = (fraction.clamp(0.0, 1.0) * (width * steps) as f64).round() as usize;
+    let (full_cells, rest) = (filled / steps, filled % steps);
+
+    let mut cells = full.to_string().repeat(full_cells);
+    if rest > 0 {
+        cells.push(partials[rest - 1]);
+    }
+    let len = full_cells + usize::from(rest > 0);
+    cells.push_str(&empty.to_string().repeat(width.saturating_sub(len)));
+    cells
+}
+
+/// Parse a colour name (e.g. `dark_green`), a hex colour (`#rrggbb`) or an ANSI colour number
+pub fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        return Some(Color::Rgb {
+            r: (value >> 16) as u8,
+            g: (value >> 8) as u8,
+            b: value as u8,
+        });
+    }
+    if let Ok(value) = name.parse::<u8>() {
+        return Some(Color::AnsiValue(value));
+    }
+    Color::try_from(name).ok()
+}
+
+/// Return the text of the field
+fn render_field(field: BarField, timer_info: &TimerInfo, direction: FillDirection) -> String {
+    match field {
+        BarField::Status => timer_info.get_human_readable(TimeFormat::default()),
+        BarField::Time => crate::utils::convert_to_time_format(
+            timer_info.get_time_left().max(0),
+            TimeFormat::default(),
+        ),
+        BarField::EndTime => match timer_info.state {
+            TimerState::Running => {
+                let end = chrono::Local::now()
+                    + chrono::Duration::seconds(timer_info.get_time_left().max(0));
+                format!("until {}", end.format("%H:%M"))
+            }
+            _ => String::new(),
+        },
+        BarField::Message => timer_info.message.clone(),
+        BarField::Percentage => {
+            format!("{:.0}%", fill_fraction(timer_info, direction) * 100.0)
+        }
+    }
+}
+
+impl ProgressBarConfig {
+    /// Return the configured colour of the bar for the state and phase of the timer
+    fn color_for(&self, timer_info: &TimerInfo) -> Option<Color> {
+        let colors = &self.colors;
+        let name = match timer_info.state {
+            TimerState::Paused => colors.paused.as_ref(),
+            TimerState::Finished => colors.finished.as_ref(),
+            TimerState::Running => None,
+        }
+        .or(match timer_info.phase {
+            TimerPhase::Work => colors.work.as_ref(),
+            TimerPhase::Break => colors.break_time.as_ref(),
+        })?;
+        parse_color(name)
+    }
+
+    /// Render the bar followed by the configured fields. A width of 0 fits the bar to the given
+    /// terminal width. Colours are only used if `color` is true.
+    pub fn render(&self, timer_info: &TimerInfo, terminal_width: usize, color: bool) -> String {
+        let text = self
+            .fields
+            .iter()
+            .map(|field| render_field(*field, timer_info, self.fill))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" · ");
+        let (left, right) = self.glyphs.borders();
+        let width = match self.width {
+            0 => terminal_width
+                .saturating_sub(
+                    left.chars().count() + right.chars().count() + text.chars().count() + 1,
+                )
+                .max(MIN_AUTO_WIDTH),
+            width => width,
+        };
+
+        let cells = render_cells(fill_fraction(timer_info, self.fill), width, self.glyphs);
+        let cells = match self.color_for(timer_info) {
+            Some(bar_color) if color => cells.with(bar_color).to_string(),
+            _ => cells,
+        };
+        format!("{}{}{} {}", left, cells, right, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_cells() {
+        assert_eq!(render_cells(0.5, 4, GlyphSet::Ascii), "##--");
+        assert_eq!(render_cells(1.5, 2, GlyphSet::Ascii), "##");
+        assert_eq!(render_cells(0.0, 3, GlyphSet::Blocks), "░░░");
+        assert_eq!(render_cells(0.5, 3, GlyphSet::Blocks), "█▌░");
+        assert_eq!(render_cells(1.0 / 16.0, 2, GlyphSet::Braille), "⡀⣀");
+        assert_eq!(render_cells(0.5, 0, GlyphSet::Blocks), "");
+    }
+
+    #[test]
+    fn test_fill_fraction() {
+        let timer_info = TimerInfo {
+            duration: 0,
+            ..Default::default()
+        };
+        assert_eq!(fill_fraction(&timer_info, FillDirection::Elapsed), 1.0);
+        assert_eq!(fill_fraction(&timer_info, FillDirection::Remaining), 0.0);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("dark_green"), Some(Color::DarkGreen));
+        assert_eq!(
+            parse_color("#ff8000"),
+            Some(Color::Rgb {
+                r: 255,
+                g: 128,
+                b: 0
+            })
+        );
+        assert_eq!(parse_color("208"), Some(Color::AnsiValue(208)));
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("nope"), None);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::config::LockConfig;
+use crate::error::*;
+use crate::utils::*;
+use lock::FailureReason;
+use notify_rust::{Notification, Timeout};
+use std::thread;
+use std::time::Duration;
+
+/// Lock the screen immediately. The configured lock command is used if there is one, otherwise
+/// the platform default locker.
+pub fn lock_screen(config: &LockConfig) -> AppResult<()> {
+    eprintln!("Locking screen...");
+
+    if let Some(command) = &config.command {
+        return run_shell_command(command);
+    }
+
+    lock::lock().map_err(|fail| {
+        AppError::new(match fail {
+            FailureReason::CannotExecute => "Cannot execute the lock command.",
+            FailureReason::LinuxCommandNotFound => {
+                "Linux command not found. The following commands are supported\
+                    \n- xdg-screensaver\
+                    \n- gnome-screensaver\
+                    \n- dm-tool\
+                    \nAlternatively, set a custom command in the `lock.command` config key."
+            }
+        })
+    })
+}
+
+/// Lock the screen after a grace period. A notification is shown when the countdown starts and
+/// the lock can be cancelled with `cancel_pending_lock` until the grace period is over.
+/// Returns false if the lock was cancelled.
+pub fn lock_screen_after_grace(config: &LockConfig, grace_time: i64) -> AppResult<bool> {
+    if grace_time <= 0 {
+        lock_screen(config)?;
+        return Ok(true);
+    }
+
+    let path = get_pending_lock_file();
+    let deadline = chrono::Utc::now().timestamp() + grace_time;
+    std::fs::write(&path, deadline.to_string())?;
+
+    let body = format!(
+        "Locking screen in {}s\nRun `pomodoro-cli lock --cancel` to cancel.",
+        grace_time
+    );
+    println!("{}", body);
+    if let Err(e) = Notification::new()
+        .summary("Pomodoro Timer")
+        .body(&body)
+        .icon("system-lock-screen")
+        .appname("pomodoro-cli")
+        .timeout(Timeout::from(Duration::from_secs(grace_time as u64)))
+        .show()
+    {
+        eprintln!("Warning: Cannot show the lock notification: {}", e);
+    }
+
+    while chrono::Utc::now().timestamp() < deadline {
+        thread::sleep(Duration::from_millis(250));
+        if !is_lock_pending(deadline) {
+            println!("Screen lock cancelled.");
+            return Ok(false);
+        }
+    }
+    std::fs::remove_file(&path).ok();
+    lock_screen(config)?;
+    Ok(true)
+}
+
+/// Return true if the pending lock file still refers to the countdown with the given deadline.
+fn is_lock_pending(deadline: i64) -> bool {
+    std::fs::read_to_string(get_pending_lock_file())
+        .is_ok_and(|contents| contents.trim() == deadline.to_string())
+}
+
+/// Cancel the pending screen lock. Returns false if there was no pending lock.
+pub fn cancel_pending_lock() -> AppResult<bool> {
+    let path = get_pending_lock_file();
+    if !path.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_file(path)?;
+    Ok(true)
+}
@@ -0,0 +1,289 @@
+use crate::app::*;
+use crate::args::TimeFormat;
+use crate::error::*;
+use crate::history::read_history;
+use crate::timer_info::{TimerInfo, TimerPhase};
+use crate::utils::parse_duration;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// The web dashboard which is served at `/`
+const DASHBOARD_HTML: &str = include_str!("../assets/dashboard.html");
+
+/// Defines the body of the `POST /api/start` request. The fields match the options of the
+/// `start` command.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StartRequest {
+    pub duration: Option<String>,
+    pub add: Option<String>,
+    pub message: Option<String>,
+    pub silent: bool,
+    pub notify: bool,
+    pub resume: bool,
+    pub lock_screen: bool,
+    #[serde(rename = "break")]
+    pub break_time: bool,
+}
+
+impl From<StartRequest> for StartOptions {
+    fn from(request: StartRequest) -> Self {
+        StartOptions {
+            duration: parse_duration(request.duration),
+            add: parse_duration(request.add),
+            message: request.message.unwrap_or_default(),
+            silent: request.silent,
+            notify: request.notify,
+            resume: request.resume,
+            lock_screen: request.lock_screen,
+            phase: if request.break_time {
+                TimerPhase::Break
+            } else {
+                TimerPhase::Work
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Defines the body of the `POST /api/add` request
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AddRequest {
+    pub time: Option<String>,
+}
+
+/// Defines the response of the status endpoints
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusResponse {
+    pub remaining: i64,
+    pub text: String,
+    pub timer: TimerInfo,
+    /// Clock of the server in milliseconds (used by `join` to correct the clock offset)
+    pub time_ms: i64,
+}
+
+impl StatusResponse {
+    fn current() -> AppResult<Self> {
+        let timer = TimerInfo::from_file_or_default()?;
+        Ok(Self {
+            remaining: timer.get_time_left().max(0),
+            text: timer.get_human_readable(TimeFormat::default()),
+            timer,
+            time_ms: chrono::Utc::now().timestamp_millis(),
+        })
+    }
+}
+
+/// Return the JSON response
+fn json_response(status: u16, body: String) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+/// Return the JSON body of an error response
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Return the status of the timer after the operation, or the error message
+fn status_after(result: AppResult<()>) -> (u16, String) {
+    let result = result.and_then(|_| StatusResponse::current());
+    match result.and_then(|status| Ok(serde_json::to_string(&status)?)) {
+        Ok(body) => (200, body),
+        Err(e) => (409, error_body(&e.to_string())),
+    }
+}
+
+/// Handle the request and return the status code and the JSON body
+fn handle_api(method: &Method, path: &str, body: &str) -> (u16, String) {
+    match (method, path) {
+        (Method::Get, "/api/status") => status_after(Ok(())),
+        (Method::Post, "/api/start") => {
+            let body = if body.trim().is_empty() { "{}" } else { body };
+            match serde_json::from_str::<StartRequest>(body) {
+                Ok(request) => status_after(start_timer(request.into())),
+                Err(e) => (400, error_body(&e.to_string())),
+            }
+        }
+        (Method::Post, "/api/add") => match serde_json::from_str::<AddRequest>(body) {
+            Ok(request) => match parse_duration(request.time) {
+                Some(seconds) => status_after(add_time(seconds)),
+                None => (400, error_body("Invalid time")),
+            },
+            Err(e) => (400, error_body(&e.to_string())),
+        },
+        (Method::Post, "/api/pause") => status_after(pause_timer()),
+        (Method::Post, "/api/stop") => status_after(stop_timer()),
+        (Method::Get, "/api/history") => match read_history() {
+            Ok(history) => (200, serde_json::to_string(&history).unwrap_or_default()),
+            Err(e) => (500, error_body(&e.to_string())),
+        },
+        _ => (404, error_body("Not found")),
+    }
+}
+
+/// Return true if the Content-Type header value is JSON (e.g. `application/json; charset=utf-8`)
+fn is_json_content_type(value: Option<&str>) -> bool {
+    value.is_some_and(|value| {
+        value
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .eq_ignore_ascii_case("application/json")
+    })
+}
+
+/// Return true if the Authorization header value carries the token (if one is required)
+fn is_authorized(value: Option<&str>, token: Option<&str>) -> bool {
+    match token {
+        Some(token) => value.and_then(|value| value.strip_prefix("Bearer ")) == Some(token),
+        None => true,
+    }
+}
+
+/// Return true if the bind address only accepts connections from this machine
+fn is_loopback(bind: &str) -> bool {
+    bind == "localhost"
+        || bind
+            .parse::<IpAddr>()
+            .is_ok_and(|address| address.is_loopback())
+}
+
+/// Return the socket address to listen on. IPv6 addresses are put in brackets (e.g. `[::1]:8080`).
+fn socket_address(bind: &str, port: u16) -> String {
+    match bind.parse::<IpAddr>() {
+        Ok(address) => SocketAddr::new(address, port).to_string(),
+        Err(_) => format!("{}:{}", bind, port),
+    }
+}
+
+/// Return the value of the request header
+fn header_value(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str().to_string())
+}
+
+/// Respond to a single request. The API requires the token (if set), and requests which change
+/// the timer must be JSON, so that other web pages cannot send them without a CORS preflight.
+fn respond(mut request: Request, token: Option<&str>) -> AppResult<()> {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let method = request.method().clone();
+    if method == Method::Get && (path == "/" || path == "/index.html") {
+        let response = Response::from_string(DASHBOARD_HTML)
+            .with_header(header("Content-Type", "text/html; charset=utf-8"));
+        return Ok(request.respond(response)?);
+    }
+    if !is_authorized(header_value(&request, "Authorization").as_deref(), token) {
+        let response = json_response(401, error_body("Unauthorized"));
+        return Ok(request.respond(response)?);
+    }
+    if method == Method::Post
+        && !is_json_content_type(header_value(&request, "Content-Type").as_deref())
+    {
+        let response = json_response(415, error_body("Content-Type must be application/json"));
+        return Ok(request.respond(response)?);
+    }
+
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body)?;
+    let (status, body) = handle_api(&method, &path, &body);
+    Ok(request.respond(json_response(status, body))?)
+}
+
+/// Serve the REST API and the web dashboard until the process is terminated. The timer is
+/// checked every second so that the alarm is triggered even if nobody polls the status.
+/// A token is required to bind to an address which is reachable from the network.
+pub fn serve(bind: &str, port: u16, token: Option<&str>) -> AppResult<()> {
+    if token.is_none() && !is_loopback(bind) {
+        return Err(AppError::new(
+            "A token is required to serve on a network address. Use `--token`.",
+        ));
+    }
+    let address = socket_address(bind, port);
+    let server = Server::http(&address)
+        .map_err(|e| AppError::new(&format!("Cannot listen on {}: {}", address, e)))?;
+    println!("Serving the dashboard on http://{}", address);
+
+    loop {
+        if let Some(request) = server.recv_timeout(Duration::from_secs(1))? {
+            if let Err(e) = respond(request, token) {
+                eprintln!("Warning: Cannot respond to the request: {}", e);
+            }
+        }
+        // The alarm is played in a separate process so that the requests are served meanwhile
+        if let Err(e) = check_timer_detached() {
+            eprintln!("Warning: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_request() {
+        let request: StartRequest =
+            serde_json::from_str(r#"{"duration": "10m", "break": true, "silent": true}"#).unwrap();
+        let options = StartOptions::from(request);
+        assert_eq!(options.duration, Some(600));
+        assert_eq!(options.phase, TimerPhase::Break);
+        assert!(options.silent);
+        assert!(!options.notify);
+    }
+
+    #[test]
+    fn test_unknown_endpoint() {
+        let (status, _) = handle_api(&Method::Get, "/api/nope", "");
+        assert_eq!(status, 404);
+        let (status, _) = handle_api(&Method::Post, "/api/start", "not json");
+        assert_eq!(status, 400);
+        let (status, _) = handle_api(&Method::Post, "/api/add", "not json");
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn test_request_checks() {
+        assert!(is_json_content_type(Some("application/json")));
+        assert!(is_json_content_type(Some(
+            "Application/JSON; charset=utf-8"
+        )));
+        assert!(!is_json_content_type(Some("text/plain")));
+        assert!(!is_json_content_type(None));
+
+        assert!(is_authorized(None, None));
+        assert!(is_authorized(Some("Bearer secret"), Some("secret")));
+        assert!(!is_authorized(Some("Bearer wrong"), Some("secret")));
+        assert!(!is_authorized(None, Some("secret")));
+
+        assert!(is_loopback("127.0.0.1"));
+        assert!(is_loopback("::1"));
+        assert!(is_loopback("localhost"));
+        assert!(!is_loopback("0.0.0.0"));
+        assert!(!is_loopback("192.168.1.2"));
+    }
+
+    #[test]
+    fn test_socket_address() {
+        assert_eq!(socket_address("127.0.0.1", 8080), "127.0.0.1:8080");
+        assert_eq!(socket_address("::1", 8080), "[::1]:8080");
+        assert_eq!(socket_address("localhost", 8080), "localhost:8080");
+    }
+}
@@ -0,0 +1,181 @@
+use crate::config::SiteBlockingConfig;
+use crate::error::*;
+use crate::focus_mode::is_focus_time;
+use crate::timer_info::TimerInfo;
+use crate::utils::{expand_home, write_file_atomically};
+use std::ops::Range;
+use std::path::Path;
+
+const BLOCK_BEGIN: &str = "# BEGIN pomodoro-cli distraction blocking";
+const BLOCK_END: &str = "# END pomodoro-cli distraction blocking";
+
+/// Return the byte range of the first managed block including the line ending of its end marker.
+/// A block without an end marker reaches to the end of the contents.
+fn block_range(contents: &str) -> Option<Range<usize>> {
+    let mut begin = None;
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        let end = offset + line.len();
+        match (line.trim(), begin) {
+            (BLOCK_BEGIN, None) => begin = Some(offset),
+            (BLOCK_END, Some(begin)) => return Some(begin..end),
+            _ => {}
+        }
+        offset = end;
+    }
+    begin.map(|begin| begin..contents.len())
+}
+
+/// Return the contents without the managed block. The rest of the contents is left unchanged.
+pub fn remove_block(contents: &str) -> String {
+    let mut output = contents.to_string();
+    while let Some(range) = block_range(&output) {
+        output.replace_range(range, "");
+    }
+    output
+}
+
+/// Return the contents with the managed block which redirects the domains (and their `www.`
+/// subdomains) to the unspecified address. The block uses the line endings of the contents.
+pub fn add_block(contents: &str, domains: &[String]) -> String {
+    let ending = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut output = remove_block(contents);
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push_str(ending);
+    }
+    output.push_str(BLOCK_BEGIN);
+    output.push_str(ending);
+    for domain in domains.iter().map(|domain| domain.trim()) {
+        if domain.is_empty() {
+            continue;
+        }
+        let mut names = vec![domain.to_string()];
+        if !domain.starts_with("www.") {
+            names.push(format!("www.{}", domain));
+        }
+        for name in names {
+            output.push_str(&format!("0.0.0.0 {}{}:: {}{}", name, ending, name, ending));
+        }
+    }
+    output.push_str(BLOCK_END);
+    output.push_str(ending);
+    output
+}
+
+/// Replace the file with the contents. The file is replaced atomically if possible, otherwise
+/// (e.g. for a bind-mounted hosts file) it is overwritten.
+fn write_hosts_file(path: &Path, contents: &str) -> AppResult<()> {
+    if write_file_atomically(path, contents).is_err() {
+        std::fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+/// Add the managed block to the hosts file while a work timer is running and remove it
+/// otherwise. A block left behind (e.g. after a crash) is removed by the next event. The file is
+/// not touched while the blocking is disabled and the file has no managed block.
+pub fn sync_site_blocking(config: &SiteBlockingConfig, timer_info: &TimerInfo) -> AppResult<()> {
+    let path = expand_home(&config.hosts_file);
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    if !config.enabled && block_range(&contents).is_none() {
+        return Ok(());
+    }
+    let block = config.enabled && !config.domains.is_empty() && is_focus_time(timer_info);
+    let new_contents = if block {
+        add_block(&contents, &config.domains)
+    } else {
+        remove_block(&contents)
+    };
+    if new_contents == contents {
+        return Ok(());
+    }
+    write_hosts_file(&path, &new_contents).map_err(|e| {
+        AppError::new(&format!(
+            "Cannot update the hosts file {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer_info::TimerState;
+
+    const HOSTS: &str = "127.0.0.1 localhost\n::1 localhost\n";
+
+    #[test]
+    fn test_add_and_remove_block() {
+        let domains = vec!["reddit.com".to_string(), "www.youtube.com".to_string()];
+        let blocked = add_block(HOSTS, &domains);
+        assert!(blocked.starts_with(HOSTS));
+        assert!(blocked.contains("0.0.0.0 reddit.com\n:: reddit.com\n"));
+        assert!(blocked.contains("0.0.0.0 www.reddit.com\n"));
+        assert!(!blocked.contains("www.www."));
+        assert_eq!(add_block(&blocked, &domains), blocked);
+        assert_eq!(remove_block(&blocked), HOSTS);
+        assert_eq!(remove_block(HOSTS), HOSTS);
+    }
+
+    #[test]
+    fn test_crlf_hosts_file() {
+        let hosts = HOSTS.replace('\n', "\r\n");
+        assert_eq!(remove_block(&hosts), hosts);
+        let blocked = add_block(&hosts, &["reddit.com".to_string()]);
+        assert!(blocked.contains("0.0.0.0 reddit.com\r\n:: reddit.com\r\n"));
+        assert!(!blocked.replace("\r\n", "").contains('\n'));
+        assert_eq!(remove_block(&blocked), hosts);
+    }
+
+    #[test]
+    fn test_no_trailing_newline() {
+        let hosts = HOSTS.trim_end();
+        assert_eq!(remove_block(hosts), hosts);
+        let blocked = add_block(hosts, &["reddit.com".to_string()]);
+        assert!(blocked.starts_with(&format!("{}\n{}\n", hosts, BLOCK_BEGIN)));
+        let unterminated = format!("{}\n{}\n0.0.0.0 reddit.com", hosts, BLOCK_BEGIN);
+        assert_eq!(remove_block(&unterminated), format!("{}\n", hosts));
+    }
+
+    #[test]
+    fn test_sync_site_blocking() -> AppResult<()> {
+        let path = std::env::temp_dir().join(format!("pomodoro-cli-hosts-{}", std::process::id()));
+        std::fs::write(&path, HOSTS)?;
+        let config = SiteBlockingConfig {
+            enabled: true,
+            hosts_file: path.to_string_lossy().to_string(),
+            domains: vec!["news.ycombinator.com".to_string()],
+        };
+        let mut timer_info = TimerInfo {
+            state: TimerState::Running,
+            start_time: chrono::Utc::now().timestamp(),
+            duration: 60,
+            ..Default::default()
+        };
+        sync_site_blocking(&config, &timer_info)?;
+        assert!(std::fs::read_to_string(&path)?.contains("0.0.0.0 news.ycombinator.com"));
+
+        timer_info.state = TimerState::Paused;
+        sync_site_blocking(&config, &timer_info)?;
+        assert_eq!(std::fs::read_to_string(&path)?, HOSTS);
+
+        // A disabled blocking leaves the file alone
+        let hosts = HOSTS.replace('\n', "\r\n");
+        std::fs::write(&path, hosts.trim_end())?;
+        let disabled = SiteBlockingConfig {
+            enabled: false,
+            ..config
+        };
+        sync_site_blocking(&disabled, &timer_info)?;
+        assert_eq!(std::fs::read_to_string(&path)?, hosts.trim_end());
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
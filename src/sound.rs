@@ -0,0 +1,151 @@
+use crate::config::SoundConfig;
+use crate::error::*;
+use crate::synth::AlarmPattern;
+use rodio::cpal::traits::HostTrait;
+use rodio::{Decoder, DeviceTrait, OutputStream, OutputStreamHandle, Sink};
+use std::path::PathBuf;
+
+/// Defines where the sound of an event comes from
+#[derive(Debug, PartialEq)]
+pub enum SoundSource {
+    Bundled,
+    File(PathBuf),
+    Pattern(AlarmPattern),
+}
+
+impl std::fmt::Display for SoundSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Bundled => write!(f, "bundled ding"),
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Pattern(pattern) => write!(f, "{} pattern", pattern.name()),
+        }
+    }
+}
+
+/// Defines the events which can trigger a sound
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoundEvent {
+    WorkEnd,
+    BreakEnd,
+    Warning,
+}
+
+impl SoundEvent {
+    pub const ALL: [SoundEvent; 3] = [Self::WorkEnd, Self::BreakEnd, Self::Warning];
+
+    /// Return the name of the event as used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::WorkEnd => "work-end",
+            Self::BreakEnd => "break-end",
+            Self::Warning => "warning",
+        }
+    }
+
+    /// Return the sound configured for the event. The configured value is either the name of a
+    /// built-in pattern or a path to a sound file.
+    pub fn source(&self, config: &SoundConfig) -> SoundSource {
+        let configured = match self {
+            Self::WorkEnd => &config.work_end,
+            Self::BreakEnd => &config.break_end,
+            Self::Warning => &config.warning,
+        };
+        if let Some(pattern) = configured.as_deref().and_then(AlarmPattern::from_name) {
+            return SoundSource::Pattern(pattern);
+        }
+        let file = match self {
+            Self::WorkEnd => config.work_end_file(),
+            Self::BreakEnd => config.break_end_file(),
+            Self::Warning => config.warning_file(),
+        };
+        file.map(SoundSource::File).unwrap_or(SoundSource::Bundled)
+    }
+}
+
+/// Return the names of the available audio output devices and the name of the default device.
+pub fn list_output_devices() -> AppResult<(Vec<String>, Option<String>)> {
+    let host = rodio::cpal::default_host();
+    let devices = host
+        .output_devices()
+        .map_err(|e| AppError::new(&format!("Cannot list the output devices: {}", e)))?;
+    let names = devices.filter_map(|device| device.name().ok()).collect();
+    let default = host
+        .default_output_device()
+        .and_then(|device| device.name().ok());
+    Ok((names, default))
+}
+
+/// Open an output stream to the audio device with the given name. In case the device is not
+/// found, the default output is used instead.
+pub fn open_output_stream(device: Option<&str>) -> AppResult<(OutputStream, OutputStreamHandle)> {
+    if let Some(name) = device {
+        let found = rodio::cpal::default_host()
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)));
+        if let Some(device) = found {
+            return Ok(OutputStream::try_from_device(&device)?);
+        }
+        eprintln!(
+            "Warning: Audio output device '{}' not found. Using the default output.",
+            name
+        );
+    }
+    Ok(OutputStream::try_default()?)
+}
+
+/// Play the sound of the given event and block until the playback is finished.
+pub fn play_sound(event: SoundEvent, config: &SoundConfig) -> AppResult<()> {
+    let (_stream, stream_handle) = open_output_stream(config.device.as_deref())?;
+    let sink = Sink::try_new(&stream_handle)?;
+    let source = event.source(config);
+    for _ in 0..config.repeat.max(1) {
+        match &source {
+            SoundSource::File(path) => {
+                let file = std::fs::File::open(path)?;
+                sink.append(Decoder::new(file)?);
+            }
+            SoundSource::Pattern(pattern) => sink.append(pattern.source()),
+            SoundSource::Bundled => {
+                let mp3 = include_bytes!("../assets/ding.mp3");
+                sink.append(Decoder::new(std::io::Cursor::new(mp3))?);
+            }
+        }
+    }
+    sink.set_volume(config.volume);
+    sink.sleep_until_end();
+    sink.clear();
+    Ok(())
+}
+
+/// Preview the sounds of all the events.
+pub fn test_sounds(config: &SoundConfig) -> AppResult<()> {
+    for event in SoundEvent::ALL {
+        println!("Playing {} sound ({})", event.name(), event.source(config));
+        play_sound(event, config)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_source() {
+        let config = SoundConfig {
+            work_end: Some("rising".to_string()),
+            warning: Some("/tmp/warning.ogg".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            SoundEvent::WorkEnd.source(&config),
+            SoundSource::Pattern(AlarmPattern::Rising)
+        );
+        assert_eq!(
+            SoundEvent::Warning.source(&config),
+            SoundSource::File(PathBuf::from("/tmp/warning.ogg"))
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::config::SoundscapeConfig;
+use crate::error::*;
+use crate::sound::open_output_stream;
+use crate::synth::SAMPLE_RATE;
+use crate::utils::expand_home;
+use clap::ValueEnum;
+use rodio::source::SineWave;
+use rodio::{Decoder, OutputStream, Sink, Source};
+use std::time::Duration;
+
+/// Defines the generated background sounds
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Ambience {
+    Tick,
+    WhiteNoise,
+    PinkNoise,
+    BrownNoise,
+}
+
+impl Ambience {
+    /// Return the ambience matching the given name (e.g. `brown-noise`), if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::from_str(name, true).ok()
+    }
+}
+
+/// Infinite noise source. White noise is generated with a xorshift generator and filtered into
+/// pink or brown noise.
+pub struct Noise {
+    ambience: Ambience,
+    seed: u32,
+    pink: [f32; 3],
+    brown: f32,
+}
+
+impl Noise {
+    pub fn new(ambience: Ambience) -> Self {
+        Self {
+            ambience,
+            seed: 0x9e37_79b9,
+            pink: [0.0; 3],
+            brown: 0.0,
+        }
+    }
+
+    fn white(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+}
+
+impl Iterator for Noise {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let white = self.white();
+        let sample = match self.ambience {
+            Ambience::PinkNoise => {
+                // Paul Kellet's economy pink noise filter
+                self.pink[0] = 0.99765 * self.pink[0] + white * 0.0990460;
+                self.pink[1] = 0.96300 * self.pink[1] + white * 0.2965164;
+                self.pink[2] = 0.57000 * self.pink[2] + white * 1.0526913;
+                (self.pink[0] + self.pink[1] + self.pink[2] + white * 0.1848) * 0.2
+            }
+            Ambience::BrownNoise => {
+                self.brown = (self.brown + white * 0.02) / 1.02;
+                self.brown * 3.5
+            }
+            _ => white * 0.5,
+        };
+        Some(sample.clamp(-1.0, 1.0))
+    }
+}
+
+impl Source for Noise {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Return one second of a clock tick: a short decaying click followed by silence.
+pub fn tick_samples() -> Vec<f32> {
+    let click = Duration::from_millis(15);
+    let mut samples: Vec<f32> = SineWave::new(2000.0).take_duration(click).collect();
+    let len = samples.len() as f32;
+    for (i, sample) in samples.iter_mut().enumerate() {
+        *sample *= 0.8 * (-6.0 * i as f32 / len).exp();
+    }
+    samples.resize(SAMPLE_RATE as usize, 0.0);
+    samples
+}
+
+/// Background audio which plays while a work timer is running.
+pub struct Soundscape {
+    _stream: OutputStream,
+    sink: Sink,
+}
+
+impl Soundscape {
+    /// Start the configured soundscape in paused state on the given output device. Returns
+    /// `None` if no soundscape is configured.
+    pub fn new(config: &SoundscapeConfig, device: Option<&str>) -> AppResult<Option<Self>> {
+        let Some(name) = &config.sound else {
+            return Ok(None);
+        };
+        let (stream, stream_handle) = open_output_stream(device)?;
+        let sink = Sink::try_new(&stream_handle)?;
+        sink.pause();
+        match Ambience::from_name(name) {
+            Some(Ambience::Tick) => sink.append(
+                rodio::buffer::SamplesBuffer::new(1, SAMPLE_RATE, tick_samples()).repeat_infinite(),
+            ),
+            Some(ambience) => sink.append(Noise::new(ambience)),
+            None => {
+                let file = std::fs::File::open(expand_home(name))?;
+                sink.append(Decoder::new_looped(file)?);
+            }
+        }
+        sink.set_volume(config.volume);
+        Ok(Some(Self {
+            _stream: stream,
+            sink,
+        }))
+    }
+
+    /// Play the soundscape if `playing` is true, otherwise pause it.
+    pub fn set_playing(&self, playing: bool) {
+        if playing && self.sink.is_paused() {
+            self.sink.play();
+        } else if !playing && !self.sink.is_paused() {
+            self.sink.pause();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_range() {
+        for ambience in [
+            Ambience::WhiteNoise,
+            Ambience::PinkNoise,
+            Ambience::BrownNoise,
+        ] {
+            assert!(Noise::new(ambience)
+                .take(SAMPLE_RATE as usize)
+                .all(|s| (-1.0..=1.0).contains(&s)));
+        }
+    }
+
+    #[test]
+    fn test_tick_samples() {
+        let samples = tick_samples();
+        assert_eq!(samples.len(), SAMPLE_RATE as usize);
+        assert_eq!(*samples.last().unwrap(), 0.0);
+    }
+}
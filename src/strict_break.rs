@@ -0,0 +1,225 @@
+use crate::app::{finish_timer, start_timer, trigger_alarm, StartOptions};
+use crate::config::Config;
+use crate::error::*;
+use crate::events::{publish_event, EventKind};
+use crate::history::record_timer_end;
+use crate::screen_lock::lock_screen;
+use crate::timer_info::{TimerInfo, TimerLock, TimerPhase, TimerState};
+use crate::utils::*;
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+use std::io::prelude::*;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+/// Defines the emergency skips used during the day (which is stored as JSON in the system
+/// cache directory)
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SkipTokens {
+    pub date: String,
+    pub used: u32,
+}
+
+impl SkipTokens {
+    /// Initialize the SkipTokens from the stored JSON file. The count is reset when the day
+    /// changes.
+    pub fn from_file_or_default(today: &str) -> AppResult<Self> {
+        let path = get_skip_tokens_file();
+        let mut tokens = Self::default();
+        if path.exists() {
+            let mut contents = String::new();
+            std::fs::File::open(path)?.read_to_string(&mut contents)?;
+            tokens = serde_json::from_str(&contents).unwrap_or_default();
+        }
+        Ok(tokens.for_day(today))
+    }
+
+    /// Return the tokens of the given day. The used count is reset if the stored day differs.
+    pub fn for_day(self, today: &str) -> Self {
+        if self.date == today {
+            return self;
+        }
+        Self {
+            date: today.to_string(),
+            used: 0,
+        }
+    }
+
+    /// Use one skip token. Returns false if all the tokens of the day are used.
+    pub fn take(&mut self, per_day: u32) -> bool {
+        if self.used >= per_day {
+            return false;
+        }
+        self.used += 1;
+        true
+    }
+
+    /// Write the SkipTokens to the JSON file.
+    pub fn write_to_file(&self) -> AppResult<()> {
+        std::fs::write(get_skip_tokens_file(), serde_json::to_string_pretty(&self)?)?;
+        Ok(())
+    }
+}
+
+/// Return the current local date (e.g. `2024-01-31`)
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Return an error if a strict break is in progress. Strict breaks can only be ended with an
+/// emergency skip.
+pub fn ensure_not_in_strict_break(timer_info: &TimerInfo) -> AppResult<()> {
+    if timer_info.is_strict_break() && !timer_info.is_time_run_out() {
+        return Err(AppError::new(
+            "A strict break is in progress. Use `pomodoro-cli skip` to end it early.",
+        ));
+    }
+    Ok(())
+}
+
+/// Start the strict break after a work timer has finished and spawn the process which enforces
+/// it.
+pub fn start_strict_break(finished: &TimerInfo, config: &Config) -> AppResult<()> {
+    start_timer(StartOptions {
+        duration: Some(config.strict_break.duration),
+        message: "Break".to_string(),
+        silent: finished.silent,
+        notify: finished.notify,
+        phase: TimerPhase::Break,
+        strict: true,
+        ..Default::default()
+    })?;
+    eprintln!(
+        "Strict break started ({} emergency skips per day).",
+        config.strict_break.skips_per_day
+    );
+    Command::new(std::env::current_exe()?)
+        .arg("enforce-break")
+        .stdout(std::process::Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// Keep the screen locked until the strict break is over. The screen is re-locked if it gets
+/// unlocked early. The screen is only locked again when the lock state confirms that it is
+/// unlocked, so that lockers do not stack up. In case the state cannot be queried, a warning is
+/// shown once.
+pub fn enforce_break() -> AppResult<()> {
+    let config = Config::from_file_or_default()?;
+    let interval = config.strict_break.relock_interval.max(1);
+    lock_screen(&config.lock)?;
+    let mut last_lock = chrono::Utc::now().timestamp();
+    let mut warned = false;
+    loop {
+        thread::sleep(Duration::from_secs(1));
+        let timer_info = TimerInfo::from_file_or_default()?;
+        if !timer_info.is_strict_break() {
+            return Ok(());
+        }
+        if timer_info.is_time_run_out() {
+            return match finish_timer()? {
+                Some(finished) => trigger_alarm(&finished),
+                None => Ok(()),
+            };
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        if now - last_lock < interval {
+            continue;
+        }
+        match is_screen_locked() {
+            Some(false) => {
+                lock_screen(&config.lock)?;
+                last_lock = now;
+            }
+            Some(true) => {}
+            None if !warned => {
+                warn_relock_unavailable();
+                warned = true;
+            }
+            None => {}
+        }
+    }
+}
+
+/// End the strict break early by using one of the emergency skips of the day.
+pub fn skip_break() -> AppResult<()> {
+    let _lock = TimerLock::acquire()?;
+    let mut timer_info = TimerInfo::from_file_or_default()?;
+    if !timer_info.is_strict_break() {
+        return Err(AppError::new("No strict break is in progress."));
+    }
+    let config = Config::from_file_or_default()?;
+    let mut tokens = SkipTokens::from_file_or_default(&today())?;
+    if !tokens.take(config.strict_break.skips_per_day) {
+        return Err(AppError::new("No emergency skips left for today."));
+    }
+    tokens.write_to_file()?;
+    record_timer_end(&mut timer_info)?;
+    timer_info.state = TimerState::Finished;
+    timer_info.write_to_file()?;
+    publish_event(EventKind::Stopped, &timer_info);
+    println!(
+        "Break skipped ({} of {} emergency skips left today).",
+        config.strict_break.skips_per_day - tokens.used,
+        config.strict_break.skips_per_day
+    );
+    Ok(())
+}
+
+/// Tell the user that the screen is not locked again during the break, because the lock state
+/// is not available
+fn warn_relock_unavailable() {
+    let body = "The lock state cannot be queried (loginctl), so the screen is not locked again \
+        if it is unlocked during the break.";
+    if let Err(e) = Notification::new()
+        .summary("Pomodoro Timer")
+        .body(body)
+        .appname("pomodoro-cli")
+        .show()
+    {
+        eprintln!("Warning: {} ({})", body, e);
+    }
+}
+
+/// Query the lock state of the session from systemd-logind. Returns `None` if the state is
+/// not available.
+fn is_screen_locked() -> Option<bool> {
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or("auto".to_string());
+    let output = Command::new("loginctl")
+        .args(["show-session", &session, "-p", "LockedHint", "--value"])
+        .output()
+        .ok()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_tokens() {
+        let mut tokens = SkipTokens::default().for_day("2024-01-01");
+        assert!(tokens.take(2));
+        assert!(tokens.take(2));
+        assert!(!tokens.take(2));
+        assert_eq!(tokens.used, 2);
+
+        let tokens = tokens.for_day("2024-01-01");
+        assert_eq!(tokens.used, 2);
+        let mut tokens = tokens.for_day("2024-01-02");
+        assert_eq!(tokens.used, 0);
+        assert!(tokens.take(2));
+    }
+
+    #[test]
+    fn test_no_skip_tokens() {
+        let mut tokens = SkipTokens::default().for_day("2024-01-01");
+        assert!(!tokens.take(0));
+    }
+}
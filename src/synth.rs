@@ -0,0 +1,200 @@
+use crate::error::*;
+use clap::ValueEnum;
+use rodio::buffer::SamplesBuffer;
+use rodio::source::SineWave;
+use rodio::Source;
+use std::path::Path;
+use std::time::Duration;
+
+/// Sample rate of the generated patterns (same as rodio's `SineWave`)
+pub const SAMPLE_RATE: u32 = 48000;
+
+/// Defines the built-in alarm patterns which are generated without any sound file
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum AlarmPattern {
+    Beep,
+    DoubleBeep,
+    Rising,
+    Falling,
+    Chime,
+    ChimeLow,
+    ChimeHigh,
+    Alert,
+}
+
+/// A single step of a pattern. A frequency of zero is silence.
+struct Tone {
+    freq: f32,
+    millis: u64,
+    /// Chime tones have an overtone and fade out exponentially
+    chime: bool,
+}
+
+const fn beep(freq: f32, millis: u64) -> Tone {
+    Tone {
+        freq,
+        millis,
+        chime: false,
+    }
+}
+
+const fn chime(freq: f32, millis: u64) -> Tone {
+    Tone {
+        freq,
+        millis,
+        chime: true,
+    }
+}
+
+const fn pause(millis: u64) -> Tone {
+    beep(0.0, millis)
+}
+
+impl AlarmPattern {
+    /// Return the pattern matching the given name (e.g. `double-beep`), if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::from_str(name, true).ok()
+    }
+
+    /// Return the name of the pattern as used in the config file.
+    pub fn name(&self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    fn tones(&self) -> Vec<Tone> {
+        match self {
+            Self::Beep => vec![
+                beep(880.0, 150),
+                pause(100),
+                beep(880.0, 150),
+                pause(100),
+                beep(880.0, 150),
+            ],
+            Self::DoubleBeep => vec![
+                beep(1046.5, 100),
+                pause(60),
+                beep(1046.5, 100),
+                pause(400),
+                beep(1046.5, 100),
+                pause(60),
+                beep(1046.5, 100),
+            ],
+            Self::Rising => vec![
+                beep(440.0, 150),
+                beep(554.4, 150),
+                beep(659.3, 150),
+                beep(880.0, 300),
+            ],
+            Self::Falling => vec![
+                beep(880.0, 150),
+                beep(659.3, 150),
+                beep(554.4, 150),
+                beep(440.0, 300),
+            ],
+            Self::Chime => vec![chime(1046.5, 600), chime(1318.5, 1200)],
+            Self::ChimeLow => vec![chime(523.3, 600), chime(659.3, 1200)],
+            Self::ChimeHigh => vec![chime(2093.0, 600), chime(2637.0, 1200)],
+            Self::Alert => vec![
+                beep(987.8, 120),
+                beep(784.0, 120),
+                beep(987.8, 120),
+                beep(784.0, 120),
+                pause(250),
+                beep(987.8, 120),
+                beep(784.0, 120),
+                beep(987.8, 120),
+                beep(784.0, 120),
+            ],
+        }
+    }
+
+    /// Generate the mono samples of the pattern.
+    pub fn samples(&self) -> Vec<f32> {
+        let mut samples = Vec::new();
+        for tone in self.tones() {
+            let duration = Duration::from_millis(tone.millis);
+            let start = samples.len();
+            if tone.freq <= 0.0 {
+                let count = (SAMPLE_RATE as u64 * tone.millis / 1000) as usize;
+                samples.resize(start + count, 0.0);
+            } else if tone.chime {
+                let overtone = SineWave::new(tone.freq * 2.0).amplify(0.25);
+                samples.extend(
+                    SineWave::new(tone.freq)
+                        .amplify(0.5)
+                        .mix(overtone)
+                        .take_duration(duration),
+                );
+                let len = (samples.len() - start) as f32;
+                for (i, sample) in samples[start..].iter_mut().enumerate() {
+                    *sample *= (-5.0 * i as f32 / len).exp();
+                }
+            } else {
+                samples.extend(
+                    SineWave::new(tone.freq)
+                        .take_duration(duration)
+                        .amplify(0.6)
+                        .fade_in(Duration::from_millis(5)),
+                );
+            }
+        }
+        samples
+    }
+
+    /// Return the pattern as a playable rodio source.
+    pub fn source(&self) -> SamplesBuffer<f32> {
+        SamplesBuffer::new(1, SAMPLE_RATE, self.samples())
+    }
+
+    /// Render the pattern into a 16-bit mono WAV file.
+    pub fn render_to_wav(&self, path: &Path) -> AppResult<()> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for sample in self.samples() {
+            writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+        }
+        writer.finalize()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(AlarmPattern::from_name("chime"), Some(AlarmPattern::Chime));
+        assert_eq!(
+            AlarmPattern::from_name("Double-Beep"),
+            Some(AlarmPattern::DoubleBeep)
+        );
+        assert_eq!(AlarmPattern::from_name("~/alarm.mp3"), None);
+        assert_eq!(AlarmPattern::DoubleBeep.name(), "double-beep");
+    }
+
+    #[test]
+    fn test_pattern_length() {
+        let samples = AlarmPattern::Beep.samples();
+        assert_eq!(samples.len(), SAMPLE_RATE as usize * 650 / 1000);
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+    }
+
+    #[test]
+    fn test_render_to_wav() -> AppResult<()> {
+        let path = std::env::temp_dir().join("pomodoro-cli-test-chime.wav");
+        AlarmPattern::Chime.render_to_wav(&path)?;
+        let reader = hound::WavReader::open(&path)?;
+        assert_eq!(reader.spec().sample_rate, SAMPLE_RATE);
+        assert_eq!(reader.len() as usize, AlarmPattern::Chime.samples().len());
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
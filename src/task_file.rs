@@ -0,0 +1,173 @@
+use crate::error::*;
+use crate::timer_info::TimerTask;
+use crate::utils::write_file_atomically;
+use std::path::{Path, PathBuf};
+
+/// Return the line without the line ending (`\n` or `\r\n`)
+pub fn line_content(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+/// Defines a text file whose lines keep their line endings, so that the lines which are not
+/// updated are written back unchanged
+#[derive(Debug)]
+pub struct LineFile {
+    path: PathBuf,
+    lines: Vec<String>,
+}
+
+impl LineFile {
+    /// Read the file. A missing file has no lines.
+    pub fn open(path: &Path) -> AppResult<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self::parse(path, &contents))
+    }
+
+    pub fn parse(path: &Path, contents: &str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            lines: contents.split_inclusive('\n').map(str::to_string).collect(),
+        }
+    }
+
+    /// Replace the file atomically with the lines
+    pub fn save(&self) -> AppResult<()> {
+        write_file_atomically(&self.path, &self.contents())
+    }
+
+    /// Return the contents of the file
+    pub fn contents(&self) -> String {
+        self.lines.concat()
+    }
+
+    /// Return the line numbers (starting from 1) with the lines without their line endings
+    pub fn lines(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| (index as u32 + 1, line_content(line)))
+    }
+
+    /// Return the line without its line ending
+    pub fn line(&self, line: u32) -> Option<&str> {
+        let index = (line as usize).checked_sub(1)?;
+        self.lines.get(index).map(|line| line_content(line))
+    }
+
+    /// Replace the text of the line. The line ending is kept.
+    pub fn update(&mut self, line: u32, text: &str) {
+        let Some(index) = (line as usize).checked_sub(1) else {
+            return;
+        };
+        if let Some(current) = self.lines.get_mut(index) {
+            let ending = &current[line_content(current).len()..];
+            *current = format!("{}{}", text, ending);
+        }
+    }
+
+    /// Remove the blank lines from the end of the file
+    pub fn trim_blank_lines(&mut self) {
+        while self.lines.last().is_some_and(|line| line.trim().is_empty()) {
+            self.lines.pop();
+        }
+    }
+
+    /// Append a line and return its line number. The line ending of the file is used.
+    pub fn append(&mut self, text: &str) -> u32 {
+        let ending = match self.lines.first() {
+            Some(line) if line.ends_with("\r\n") => "\r\n",
+            _ => "\n",
+        };
+        if let Some(last) = self.lines.last_mut() {
+            if !last.ends_with('\n') {
+                last.push_str(ending);
+            }
+        }
+        self.lines.push(format!("{}{}", text, ending));
+        self.lines.len() as u32
+    }
+}
+
+/// Defines a task line of a task file. The line number is used as the ID of the task.
+pub trait FileTask: Clone {
+    fn line(&self) -> u32;
+
+    /// Return true if the task is complete
+    fn is_done(&self) -> bool;
+
+    /// Return the snapshot of the task which is stored in the timer info
+    fn snapshot(&self) -> TimerTask;
+}
+
+/// Defines a task file (todo.txt or Markdown) which the tasks are read from and written to
+pub trait TaskFile {
+    type Item: FileTask;
+
+    /// Return the task lines of the file
+    fn items(&self) -> Vec<Self::Item>;
+
+    /// Add an open task and return its line number
+    fn add(&mut self, title: &str, estimate: u32) -> AppResult<u32>;
+
+    /// Set the estimated pomodoros of the task
+    fn set_estimate(&mut self, line: u32, estimate: u32) -> AppResult<()>;
+
+    /// Mark the task complete
+    fn complete(&mut self, item: &Self::Item);
+
+    /// Write the file back
+    fn save(&self) -> AppResult<()>;
+
+    /// Return the task on the given line
+    fn get(&self, line: u32) -> AppResult<Self::Item> {
+        self.items()
+            .into_iter()
+            .find(|item| item.line() == line)
+            .ok_or_else(|| AppError::new(&format!("No task on line {}.", line)))
+    }
+
+    /// Find the task of the snapshot. The line is checked first, and the task is searched by its
+    /// title if the file has been edited since.
+    fn find(&self, task: &TimerTask) -> Option<Self::Item> {
+        let items = self.items();
+        let title = |item: &Self::Item| item.snapshot().title;
+        items
+            .iter()
+            .find(|item| item.line() == task.id && title(item) == task.title)
+            .or_else(|| {
+                items
+                    .iter()
+                    .find(|item| !item.is_done() && title(item) == task.title)
+            })
+            .cloned()
+    }
+
+    /// Return the snapshots of the open tasks
+    fn open_tasks(&self) -> Vec<TimerTask> {
+        self.items()
+            .iter()
+            .filter(|item| !item.is_done())
+            .map(FileTask::snapshot)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_file() {
+        let mut file = LineFile::parse(Path::new("tasks"), "First  \r\nSecond\r\n\r\n");
+        assert_eq!(file.line(1), Some("First  "));
+        file.update(2, "Second line");
+        file.trim_blank_lines();
+        assert_eq!(file.append("Third"), 3);
+        assert_eq!(file.contents(), "First  \r\nSecond line\r\nThird\r\n");
+        assert_eq!(file.line(0), None);
+    }
+}
@@ -0,0 +1,488 @@
+use crate::args::TaskCommand;
+use crate::config::{Config, TaskSource, TasksConfig};
+use crate::error::*;
+use crate::markdown_tasks::{self, MarkdownFile};
+use crate::task_file::{FileTask, TaskFile};
+use crate::timer_info::{TimerInfo, TimerTask};
+use crate::todo_txt::{self, TodoFile};
+use crate::utils::*;
+use serde::{Deserialize, Serialize};
+use std::io::prelude::*;
+
+/// Defines a task which work sessions can be linked to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Task {
+    pub id: u32,
+    pub title: String,
+    /// Estimated number of pomodoros (0 if not estimated)
+    #[serde(default)]
+    pub estimate: u32,
+    /// Number of completed pomodoros
+    #[serde(default)]
+    pub completed: u32,
+    #[serde(default)]
+    pub done: bool,
+    pub created: i64,
+}
+
+impl Task {
+    /// Return the snapshot of the task which is stored in the timer info
+    pub fn snapshot(&self) -> TimerTask {
+        TimerTask {
+            id: self.id,
+            title: self.title.clone(),
+            completed: self.completed,
+            estimate: self.estimate,
+            source: TaskSource::Local,
+        }
+    }
+}
+
+/// Defines the task list (which is stored as JSON in the data directory)
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TaskStore {
+    pub next_id: u32,
+    /// ID of the task which new work sessions are linked to
+    pub selected: Option<u32>,
+    /// Selected task of a todo.txt or Markdown file. The title is kept so that the task is found
+    /// again after the lines of the file have moved.
+    pub selected_item: Option<TimerTask>,
+    pub tasks: Vec<Task>,
+}
+
+impl Default for TaskStore {
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            selected: None,
+            selected_item: None,
+            tasks: Vec::new(),
+        }
+    }
+}
+
+impl TaskStore {
+    /// Initialize the TaskStore from the stored JSON file. An error is returned if the file
+    /// cannot be parsed, so that the task list is never overwritten with an empty one.
+    pub fn from_file_or_default() -> AppResult<Self> {
+        let path = get_task_store_file();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let mut contents = String::new();
+        std::fs::File::open(path)?.read_to_string(&mut contents)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Write the TaskStore to the JSON file. The file is replaced atomically.
+    pub fn write_to_file(&self) -> AppResult<()> {
+        let path = get_task_store_file();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        write_file_atomically(&path, &serde_json::to_string_pretty(&self)?)
+    }
+
+    /// Add a new task and return its ID
+    pub fn add(&mut self, title: &str, estimate: u32) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.tasks.push(Task {
+            id,
+            title: title.to_string(),
+            estimate,
+            completed: 0,
+            done: false,
+            created: chrono::Utc::now().timestamp(),
+        });
+        id
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Task> {
+        self.tasks.iter().find(|task| task.id == id)
+    }
+
+    /// Return the task with the given ID, or an error if there is none
+    pub fn get_mut(&mut self, id: u32) -> AppResult<&mut Task> {
+        self.tasks
+            .iter_mut()
+            .find(|task| task.id == id)
+            .ok_or_else(|| AppError::new(&format!("No task with ID {}.", id)))
+    }
+
+    /// Return the selected task (if it is still open)
+    pub fn selected(&self) -> Option<&Task> {
+        self.selected
+            .and_then(|id| self.get(id))
+            .filter(|task| !task.done)
+    }
+
+    /// Select the open task with the given ID
+    pub fn select(&mut self, id: u32) -> AppResult<()> {
+        if self.get_mut(id)?.done {
+            return Err(AppError::new(&format!("Task {} is already done.", id)));
+        }
+        self.selected = Some(id);
+        Ok(())
+    }
+
+    /// Mark the task as done. The selection is cleared if the task was selected.
+    pub fn mark_done(&mut self, id: u32) -> AppResult<()> {
+        self.get_mut(id)?.done = true;
+        if self.selected == Some(id) {
+            self.selected = None;
+        }
+        Ok(())
+    }
+}
+
+/// Return the fuzzy match score of the text (higher is better), or `None` if the characters of
+/// the query do not appear in the text in order. A substring match beats any other match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query = query.to_lowercase();
+    let text = text.to_lowercase();
+    if let Some(position) = text.find(&query) {
+        return Some(1000 - position as i64);
+    }
+    let mut chars = text.chars().enumerate();
+    let mut last: Option<usize> = None;
+    let mut score = 0;
+    for wanted in query.chars() {
+        let (index, _) = chars.find(|(_, c)| *c == wanted)?;
+        if let Some(last) = last {
+            score -= (index - last - 1) as i64;
+        }
+        last = Some(index);
+    }
+    Some(score)
+}
+
+/// Return the task which matches the query best (the first one wins a tie)
+pub fn best_match(query: &str, tasks: Vec<TimerTask>) -> Option<TimerTask> {
+    let mut best: Option<(i64, TimerTask)> = None;
+    for task in tasks {
+        if let Some(score) = fuzzy_score(query, &task.title) {
+            let better = match &best {
+                Some((best, _)) => score > *best,
+                None => true,
+            };
+            if better {
+                best = Some((score, task));
+            }
+        }
+    }
+    best.map(|(_, task)| task)
+}
+
+/// Return the open tasks of the configured source
+fn open_tasks(config: &TasksConfig) -> AppResult<Vec<TimerTask>> {
+    Ok(match config.source {
+        TaskSource::Local => TaskStore::from_file_or_default()?
+            .tasks
+            .iter()
+            .filter(|task| !task.done)
+            .map(Task::snapshot)
+            .collect(),
+        TaskSource::TodoTxt => todo_file(config)?.open_tasks(),
+        TaskSource::Markdown => markdown_file(config)?.open_tasks(),
+    })
+}
+
+/// Open the configured todo.txt file
+fn todo_file(config: &TasksConfig) -> AppResult<TodoFile> {
+    TodoFile::open(&expand_home(&config.todo_file))
+}
+
+/// Open the configured Markdown file
+fn markdown_file(config: &TasksConfig) -> AppResult<MarkdownFile> {
+    MarkdownFile::open(&expand_home(&config.markdown_file), &config.tally_marker)
+}
+
+/// Return the selected task of the configured source with its current snapshot. The selected
+/// item of a file is searched by its title, and an error is returned if it is no longer open.
+fn resolve_selection(config: &TasksConfig, store: &TaskStore) -> AppResult<Option<TimerTask>> {
+    let selected = match (config.source, &store.selected_item) {
+        (TaskSource::Local, _) => return Ok(store.selected().map(Task::snapshot)),
+        (source, Some(item)) if item.source == source => item,
+        _ => return Ok(None),
+    };
+    let task = if config.source == TaskSource::TodoTxt {
+        find_open(&todo_file(config)?, selected)
+    } else {
+        find_open(&markdown_file(config)?, selected)
+    };
+    task.map(Some).ok_or_else(|| {
+        AppError::new(&format!(
+            "The selected task '{}' is no longer open. Select another task.",
+            selected.title
+        ))
+    })
+}
+
+/// Return the current snapshot of the task if it is still open in the file
+fn find_open(file: &impl TaskFile, task: &TimerTask) -> Option<TimerTask> {
+    file.find(task)
+        .filter(|item| !item.is_done())
+        .map(|item| item.snapshot())
+}
+
+/// Select the task of the source which it belongs to
+fn set_selection(store: &mut TaskStore, task: &TimerTask) {
+    match task.source {
+        TaskSource::Local => store.selected = Some(task.id),
+        TaskSource::TodoTxt | TaskSource::Markdown => store.selected_item = Some(task.clone()),
+    }
+}
+
+/// Pick the task for `start --task` and select it. The query is matched fuzzily against the
+/// open tasks. Without a query, the top-priority task of a todo.txt file, or the selected (or
+/// else the first) open task of the other sources is picked.
+pub fn pick_task(config: &TasksConfig, query: Option<&str>) -> AppResult<TimerTask> {
+    let mut store = TaskStore::from_file_or_default()?;
+    let task = match (query, config.source) {
+        (Some(query), _) => best_match(query, open_tasks(config)?)
+            .ok_or_else(|| AppError::new(&format!("No open task matches '{}'.", query)))?,
+        (None, TaskSource::Local | TaskSource::Markdown) => {
+            match resolve_selection(config, &store)? {
+                Some(task) => task,
+                None => open_tasks(config)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| AppError::new("No open tasks."))?,
+            }
+        }
+        (None, TaskSource::TodoTxt) => todo_txt::top_priority(&todo_file(config)?.items())
+            .map(|item| item.snapshot())
+            .ok_or_else(|| AppError::new("No open tasks in the todo.txt file."))?,
+    };
+    set_selection(&mut store, &task);
+    store.write_to_file()?;
+    Ok(task)
+}
+
+/// Return the snapshot of the selected task, which a new work session is linked to
+pub fn selected_task() -> AppResult<Option<TimerTask>> {
+    let store = TaskStore::from_file_or_default()?;
+    resolve_selection(&Config::from_file_or_default()?.tasks, &store)
+}
+
+/// Count a completed pomodoro for the task which the work session is linked to, and update the
+/// snapshot in the timer info.
+pub fn record_pomodoro(timer_info: &mut TimerInfo) -> AppResult<()> {
+    let Some(linked) = &timer_info.task else {
+        return Ok(());
+    };
+    match linked.source {
+        TaskSource::Local => {
+            let mut store = TaskStore::from_file_or_default()?;
+            let Ok(task) = store.get_mut(linked.id) else {
+                // The task has been removed from the store
+                return Ok(());
+            };
+            task.completed += 1;
+            timer_info.task = Some(task.snapshot());
+            store.write_to_file()
+        }
+        TaskSource::TodoTxt => {
+            let config = Config::from_file_or_default()?.tasks;
+            let path = expand_home(&config.todo_file);
+            if let Some(task) = todo_txt::record_pomodoro(&path, linked)? {
+                timer_info.task = Some(task);
+            }
+            Ok(())
+        }
+        TaskSource::Markdown => {
+            let config = Config::from_file_or_default()?.tasks;
+            let path = expand_home(&config.markdown_file);
+            if let Some(task) =
+                markdown_tasks::record_pomodoro(&path, &config.tally_marker, linked)?
+            {
+                timer_info.task = Some(task);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Print a row of the task list. The selected task is marked with `*`.
+fn print_task(task: &TimerTask, done: bool, selected: bool) {
+    println!(
+        "{} {:>3} [{}] {}",
+        if selected { '*' } else { ' ' },
+        task.id,
+        if done { 'x' } else { ' ' },
+        task.display()
+    );
+}
+
+/// Run the subcommand of the task command on the configured task source
+pub fn run_task_command(action: &TaskCommand) -> AppResult<()> {
+    let config = Config::from_file_or_default()?.tasks;
+    match config.source {
+        TaskSource::Local => run_local_command(action),
+        TaskSource::TodoTxt => run_file_command(action, &config, todo_file(&config)?),
+        TaskSource::Markdown => run_file_command(action, &config, markdown_file(&config)?),
+    }
+}
+
+fn run_local_command(action: &TaskCommand) -> AppResult<()> {
+    let mut store = TaskStore::from_file_or_default()?;
+    match action {
+        TaskCommand::Add {
+            title,
+            estimate,
+            select,
+        } => {
+            let id = store.add(title, *estimate);
+            if *select {
+                store.select(id)?;
+            }
+            println!("Added task {}: {}", id, title);
+        }
+        TaskCommand::List { all } => {
+            for task in store.tasks.iter().filter(|task| *all || !task.done) {
+                print_task(&task.snapshot(), task.done, store.selected == Some(task.id));
+            }
+            return Ok(());
+        }
+        TaskCommand::Done { id } => {
+            let id = id
+                .or(store.selected)
+                .ok_or_else(|| AppError::new("No task is selected."))?;
+            store.mark_done(id)?;
+            println!("Task {} done.", id);
+        }
+        TaskCommand::Estimate { id, pomodoros } => {
+            store.get_mut(*id)?.estimate = *pomodoros;
+        }
+        TaskCommand::Select { id, clear: _ } => match id {
+            Some(id) => store.select(*id)?,
+            None => store.selected = None,
+        },
+    }
+    store.write_to_file()
+}
+
+/// Run the subcommand on a todo.txt or Markdown file. The selected task is stored in the task
+/// store.
+fn run_file_command(
+    action: &TaskCommand,
+    config: &TasksConfig,
+    mut file: impl TaskFile,
+) -> AppResult<()> {
+    let mut store = TaskStore::from_file_or_default()?;
+    match action {
+        TaskCommand::Add {
+            title,
+            estimate,
+            select,
+        } => {
+            let line = file.add(title, *estimate)?;
+            file.save()?;
+            if *select {
+                set_selection(&mut store, &file.get(line)?.snapshot());
+                store.write_to_file()?;
+            }
+            println!("Added task {}: {}", line, title);
+        }
+        TaskCommand::List { all } => {
+            let selected = resolve_selection(config, &store).ok().flatten();
+            for item in file.items().iter().filter(|item| *all || !item.is_done()) {
+                print_task(
+                    &item.snapshot(),
+                    item.is_done(),
+                    selected.as_ref().is_some_and(|task| task.id == item.line()),
+                );
+            }
+        }
+        TaskCommand::Done { id } => {
+            // A stale selection only matters when no ID is given
+            let selected = match id {
+                Some(_) => resolve_selection(config, &store).unwrap_or(None),
+                None => resolve_selection(config, &store)?,
+            };
+            let line = id
+                .or(selected.as_ref().map(|task| task.id))
+                .ok_or_else(|| AppError::new("No task is selected."))?;
+            let item = file.get(line)?;
+            file.complete(&item);
+            file.save()?;
+            if selected.is_some_and(|task| task.id == line) {
+                store.selected_item = None;
+                store.write_to_file()?;
+            }
+            println!("Task {} done.", line);
+        }
+        TaskCommand::Estimate { id, pomodoros } => {
+            file.set_estimate(*id, *pomodoros)?;
+            file.save()?;
+        }
+        TaskCommand::Select { id, clear: _ } => {
+            store.selected_item = match id {
+                Some(id) => {
+                    let item = file.get(*id)?;
+                    if item.is_done() {
+                        return Err(AppError::new(&format!("Task {} is already done.", id)));
+                    }
+                    Some(item.snapshot())
+                }
+                None => None,
+            };
+            store.write_to_file()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_select() {
+        let mut store = TaskStore::default();
+        let first = store.add("Write RFC", 4);
+        let second = store.add("Review PRs", 0);
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(store.selected(), None);
+
+        store.select(first).unwrap();
+        assert_eq!(store.selected().unwrap().title, "Write RFC");
+        assert!(store.select(3).is_err());
+    }
+
+    #[test]
+    fn test_mark_done() {
+        let mut store = TaskStore::default();
+        let id = store.add("Write RFC", 4);
+        store.select(id).unwrap();
+        store.mark_done(id).unwrap();
+        assert_eq!(store.selected, None);
+        assert!(store.get(id).unwrap().done);
+        assert!(store.select(id).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_display() {
+        let mut store = TaskStore::default();
+        let id = store.add("Write RFC", 4);
+        store.get_mut(id).unwrap().completed = 2;
+        assert_eq!(store.get(id).unwrap().snapshot().display(), "Write RFC 2/4");
+        store.get_mut(id).unwrap().estimate = 0;
+        assert_eq!(store.get(id).unwrap().snapshot().display(), "Write RFC 2");
+    }
+
+    #[test]
+    fn test_best_match() {
+        let mut store = TaskStore::default();
+        store.add("Write RFC", 0);
+        store.add("Review the release notes", 0);
+        store.add("Fix the build", 0);
+        let tasks = || store.tasks.iter().map(Task::snapshot).collect::<Vec<_>>();
+        assert_eq!(best_match("rfc", tasks()).unwrap().id, 1);
+        assert_eq!(best_match("rvw", tasks()).unwrap().id, 2);
+        assert_eq!(best_match("fxbld", tasks()).unwrap().id, 3);
+        assert_eq!(best_match("zzz", tasks()), None);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::app::get_status;
+use crate::error::*;
+use crate::server::StatusResponse;
+use crate::timer_info::TimerInfo;
+use std::thread;
+use std::time::Duration;
+
+/// Longest delay between the attempts to reconnect to the host (in seconds)
+const MAX_RETRY_DELAY: u64 = 30;
+
+/// Defines the local preferences of a joined timer
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JoinOptions {
+    pub silent: bool,
+    pub notify: bool,
+}
+
+/// Return the status URL of the host (`host:port` or a full `http://` URL)
+pub fn status_url(host: &str) -> String {
+    let host = host.trim_end_matches('/');
+    if host.starts_with("http://") || host.starts_with("https://") {
+        format!("{}/api/status", host)
+    } else {
+        format!("http://{}/api/status", host)
+    }
+}
+
+/// Return the offset of the host clock in milliseconds. The host time is assumed to be taken in
+/// the middle of the request.
+pub fn clock_offset(request_start_ms: i64, request_end_ms: i64, host_ms: i64) -> i64 {
+    host_ms - (request_start_ms + request_end_ms) / 2
+}
+
+/// Return the local copy of the host timer with the timestamps converted to the local clock.
+pub fn mirror_timer(host_timer: &TimerInfo, offset_ms: i64, options: JoinOptions) -> TimerInfo {
+    let offset = (offset_ms as f64 / 1000.0).round() as i64;
+    TimerInfo {
+        start_time: host_timer.start_time - offset,
+        pause_time: host_timer.pause_time - offset,
+        session_start: host_timer.session_start - offset,
+        silent: options.silent,
+        notify: options.notify,
+        wait: false,
+        lock_screen: false,
+        strict: false,
+        task: None,
+        interruptions: Vec::new(),
+        ..host_timer.clone()
+    }
+}
+
+/// Fetch the status of the host and return it with the clock offset
+fn fetch_status(
+    agent: &ureq::Agent,
+    url: &str,
+    token: Option<&str>,
+) -> AppResult<(StatusResponse, i64)> {
+    let request_start = chrono::Utc::now().timestamp_millis();
+    let mut request = agent.get(url);
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+    let status: StatusResponse = request
+        .call()
+        .map_err(|e| AppError::new(&e.to_string()))?
+        .into_json()?;
+    let request_end = chrono::Utc::now().timestamp_millis();
+    let offset = clock_offset(request_start, request_end, status.time_ms);
+    Ok((status, offset))
+}
+
+/// Mirror the timer of the host (started with `serve`) until the process is terminated. The local
+/// timer keeps running while the host cannot be reached, and the alarm is triggered locally.
+pub fn join_timer(host: &str, options: JoinOptions, token: Option<&str>) -> AppResult<()> {
+    let url = status_url(host);
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(5))
+        .build();
+    let mut last_snapshot: Option<TimerInfo> = None;
+    let mut connected = false;
+    let mut retry_delay = 1;
+    loop {
+        match fetch_status(&agent, &url, token) {
+            Ok((status, offset)) => {
+                if !connected {
+                    println!("Joined the timer on {} (clock offset {} ms)", host, offset);
+                    connected = true;
+                    retry_delay = 1;
+                }
+                // Only changes of the host timer are mirrored so that the local alarm is not
+                // overwritten by a host which has not noticed the end of the timer yet
+                if last_snapshot.as_ref() != Some(&status.timer) {
+                    match mirror_timer(&status.timer, offset, options).write_to_file() {
+                        Ok(()) => last_snapshot = Some(status.timer),
+                        Err(e) => eprintln!("Warning: Cannot mirror the timer: {}", e),
+                    }
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
+            Err(e) => {
+                if connected || last_snapshot.is_none() {
+                    eprintln!("Cannot reach the host ({}). Retrying...", e);
+                }
+                connected = false;
+                thread::sleep(Duration::from_secs(retry_delay));
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+        if let Err(e) = get_status(None, None) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TaskSource;
+    use crate::timer_info::{Interruption, InterruptionKind, TimerTask};
+
+    #[test]
+    fn test_status_url() {
+        assert_eq!(
+            status_url("192.168.1.2:8080"),
+            "http://192.168.1.2:8080/api/status"
+        );
+        assert_eq!(
+            status_url("https://pomo.example.com/"),
+            "https://pomo.example.com/api/status"
+        );
+    }
+
+    #[test]
+    fn test_mirror_timer() {
+        assert_eq!(clock_offset(1000, 1200, 3100), 2000);
+        let host_timer = TimerInfo {
+            start_time: 1000,
+            pause_time: 1000,
+            session_start: 1000,
+            wait: true,
+            ..Default::default()
+        };
+        let options = JoinOptions {
+            silent: true,
+            notify: false,
+        };
+        let timer = mirror_timer(&host_timer, 2400, options);
+        assert_eq!(timer.start_time, 998);
+        assert_eq!(timer.session_start, 998);
+        assert!(timer.silent);
+        assert!(!timer.wait);
+        assert_eq!(timer.duration, host_timer.duration);
+
+        // The task and the interruptions belong to the host
+        let host_timer = TimerInfo {
+            task: Some(TimerTask {
+                id: 1,
+                title: "Write RFC".to_string(),
+                completed: 0,
+                estimate: 0,
+                source: TaskSource::Local,
+            }),
+            interruptions: vec![Interruption {
+                time: 1000,
+                kind: InterruptionKind::External,
+                note: String::new(),
+            }],
+            ..host_timer
+        };
+        let timer = mirror_timer(&host_timer, 0, options);
+        assert_eq!(timer.task, None);
+        assert!(timer.interruptions.is_empty());
+    }
+}
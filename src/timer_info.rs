@@ -1,23 +1,81 @@
 use crate::args::TimeFormat;
+use crate::config::TaskSource;
 use crate::error::*;
 use crate::utils::*;
 use serde::{Deserialize, Serialize};
-use std::fs::File;
+use std::fs::OpenOptions;
 use std::io::prelude::*;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
 /// The default duration of the timer in seconds
 pub const DEFAULT_TIMER_DURATION: i64 = 25 * 60;
 
+/// The default duration of a break timer in seconds
+pub const DEFAULT_BREAK_DURATION: i64 = 5 * 60;
+
 /// Defines the state of the timer
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
 pub enum TimerState {
     Running,
     Paused,
     Finished,
 }
 
+/// Defines the phase of the pomodoro cycle the timer is measuring
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, Clone, Copy)]
+pub enum TimerPhase {
+    #[default]
+    Work,
+    Break,
+}
+
+/// Defines the source of an interruption during a work session
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum InterruptionKind {
+    /// Self-inflicted (e.g. checking the chat)
+    Internal,
+    /// Caused by someone else (e.g. a colleague)
+    External,
+}
+
+/// Defines an interruption recorded during the session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interruption {
+    pub time: i64,
+    pub kind: InterruptionKind,
+    pub note: String,
+}
+
+/// Defines the task which the work session is linked to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimerTask {
+    pub id: u32,
+    pub title: String,
+    /// Number of completed pomodoros
+    pub completed: u32,
+    /// Estimated number of pomodoros (0 if not estimated)
+    pub estimate: u32,
+    /// Where the task is stored (the ID of a todo.txt task is its line number)
+    #[serde(default)]
+    pub source: TaskSource,
+}
+
+impl TimerTask {
+    /// Return the title with the pomodoro count (e.g. `Write RFC 2/4`)
+    pub fn display(&self) -> String {
+        if self.estimate > 0 {
+            format!("{} {}/{}", self.title, self.completed, self.estimate)
+        } else {
+            format!("{} {}", self.title, self.completed)
+        }
+    }
+}
+
 /// Defines the timer info data structure (which is stored as JSON in system cache directory)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TimerInfo {
     pub state: TimerState,
     pub start_time: i64,
@@ -28,6 +86,18 @@ pub struct TimerInfo {
     pub notify: bool,
     pub wait: bool,
     pub lock_screen: bool,
+    #[serde(default)]
+    pub phase: TimerPhase,
+    #[serde(default)]
+    pub warned: bool,
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub session_start: i64,
+    #[serde(default)]
+    pub interruptions: Vec<Interruption>,
+    #[serde(default)]
+    pub task: Option<TimerTask>,
 }
 
 #[derive(Serialize)]
@@ -38,6 +108,47 @@ pub struct WaybarTimerInfo {
     pub percentage: f64,
 }
 
+/// Time after which the timer lock is considered stale (e.g. its holder was killed)
+const STALE_LOCK_TIME: Duration = Duration::from_secs(10);
+
+/// Holds the timer lock file for as long as it is alive. Several commands may poll the timer at
+/// the same time (e.g. `status`, `serve` and `tui`), so the timer is only started or ended while
+/// the lock is held. The lock file is created with `create_new`, so only one holder can exist.
+pub struct TimerLock {
+    path: PathBuf,
+}
+
+impl TimerLock {
+    /// Wait until the timer lock is acquired. A stale lock is removed.
+    pub fn acquire() -> AppResult<Self> {
+        let path = get_timer_lock_file();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .is_ok_and(|modified| {
+                            modified.elapsed().unwrap_or_default() > STALE_LOCK_TIME
+                        });
+                    if stale {
+                        std::fs::remove_file(&path).ok();
+                    } else {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for TimerLock {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
 /// Implement default for TimerInfo
 impl Default for TimerInfo {
     fn default() -> Self {
@@ -52,6 +163,12 @@ impl Default for TimerInfo {
             notify: false,
             wait: false,
             lock_screen: false,
+            phase: TimerPhase::Work,
+            warned: false,
+            strict: false,
+            session_start: start_time,
+            interruptions: Vec::new(),
+            task: None,
         }
     }
 }
@@ -85,25 +202,75 @@ impl TimerInfo {
         self.state == TimerState::Paused
     }
 
+    /// Return the time when the session was originally started. Unlike `start_time`, this is
+    /// not moved forward when the timer is resumed.
+    pub fn session_start_time(&self) -> i64 {
+        if self.session_start > 0 {
+            self.session_start
+        } else {
+            self.start_time
+        }
+    }
+
+    /// Return true if the timer is a running strict break
+    pub fn is_strict_break(&self) -> bool {
+        self.is_running() && self.strict && self.phase == TimerPhase::Break
+    }
+
     pub fn is_time_run_out(&self) -> bool {
         self.get_time_left() < 0
     }
 
+    /// Return true if the running timer has reached the warning time and the warning has not
+    /// been given yet
+    pub fn is_warning_due(&self, warning_time: i64) -> bool {
+        self.is_running()
+            && !self.warned
+            && warning_time > 0
+            && self.get_time_left() <= warning_time
+    }
+
     /// Returns the time left in the timer in seconds.
     pub fn get_time_left(&self) -> i64 {
         self.duration - self.get_time_elapsed()
     }
 
     pub fn get_percentage(&self) -> f64 {
+        if self.duration <= 0 {
+            return 0.0;
+        }
         (self.get_time_left() as f64 / self.duration as f64) * 100.0
     }
 
+    /// Returns the number of internal and external interruptions of the session
+    pub fn interruption_counts(&self) -> (u32, u32) {
+        self.interruptions
+            .iter()
+            .fold(
+                (0, 0),
+                |(internal, external), interruption| match interruption.kind {
+                    InterruptionKind::Internal => (internal + 1, external),
+                    InterruptionKind::External => (internal, external + 1),
+                },
+            )
+    }
+
+    /// Returns the linked task and the message of the timer
+    pub fn get_label(&self) -> String {
+        match &self.task {
+            Some(task) if self.message.is_empty() => task.display(),
+            Some(task) => format!("{} - {}", task.display(), self.message),
+            None => self.message.clone(),
+        }
+    }
+
     /// Returns the info in human readable format.
     pub fn get_human_readable(&self, time_format: TimeFormat) -> String {
         let mut text = convert_to_time_format(self.get_time_left(), time_format);
-        if !self.message.is_empty() {
+        let label = self.get_label();
+        if !label.is_empty() {
             match self.state {
-                TimerState::Running => text = format!("{} - {}", text, self.message),
+                TimerState::Running => text = format!("{} - {}", text, label),
                 TimerState::Paused => text = format!("{} - Paused", text),
                 TimerState::Finished => text = format!("{} - Time is up!", text),
             }
@@ -126,8 +293,18 @@ impl TimerInfo {
                 convert_to_time_format(self.get_time_elapsed(), time_format)
             ),
             TimerState::Finished => "Finished".to_string(),
-        }
-        .to_string();
+        };
+        let tooltip = match &self.task {
+            Some(task) => format!("{}\nTask: {}", tooltip, task.display()),
+            None => tooltip,
+        };
+        let tooltip = match self.interruption_counts() {
+            (0, 0) => tooltip,
+            (internal, external) => format!(
+                "{}\nInterruptions: {} internal, {} external",
+                tooltip, internal, external
+            ),
+        };
         let class = match self.state {
             TimerState::Running => "running",
             TimerState::Paused => "paused",
@@ -139,29 +316,47 @@ impl TimerInfo {
             class: class.to_string(),
             percentage: self.get_percentage(),
         };
-        return Ok(serde_json::to_string(&waybar_info)?);
+        Ok(serde_json::to_string(&waybar_info)?)
     }
 
     /// Returns the time elapsed since start in seconds.
     pub fn get_time_elapsed(&self) -> i64 {
         match self.state {
-            TimerState::Finished => return self.duration,
-            TimerState::Paused => return self.pause_time - self.start_time,
+            TimerState::Finished => self.duration,
+            TimerState::Paused => self.pause_time - self.start_time,
             TimerState::Running => {
                 let now = chrono::Utc::now().timestamp();
                 let time_elapsed = now - self.start_time;
-                return i64::max(0, time_elapsed);
+                i64::max(0, time_elapsed)
             }
         }
     }
 
-    /// Write the TimerInfo to the JSON file.
+    /// Record an interruption of the running or paused work session. Returns the updated timer
+    /// info. The file is read right before it is written and only the interruption is added, so
+    /// that the process guard never writes back a timer which was paused or stopped meanwhile.
+    pub fn record_interruption(kind: InterruptionKind, note: String) -> AppResult<Self> {
+        let mut timer_info = Self::from_file_or_default()?;
+        if !Self::info_file_exists()
+            || timer_info.phase != TimerPhase::Work
+            || timer_info.state == TimerState::Finished
+        {
+            return Err(AppError::new("No work session is in progress."));
+        }
+        timer_info.interruptions.push(Interruption {
+            time: chrono::Utc::now().timestamp(),
+            kind,
+            note,
+        });
+        timer_info.write_to_file()?;
+        Ok(timer_info)
+    }
+
+    /// Write the TimerInfo to the JSON file. The file is replaced atomically so that readers
+    /// never see a partially written file, even if the process is killed.
     pub fn write_to_file(&self) -> AppResult<()> {
-        let path = get_timer_info_file();
-        let mut file = File::create(path)?;
         let json = serde_json::to_string_pretty(&self)?;
-        file.write_all(json.as_bytes())?;
-        Ok(())
+        write_file_atomically(&get_timer_info_file(), &json)
     }
 
     /// Remove the JSON file from the system cache directory.
@@ -175,7 +370,6 @@ impl TimerInfo {
     }
 
     /// Returns true if the JSON file exists in the system cache directory.
-    #[allow(dead_code)]
     pub fn info_file_exists() -> bool {
         let path = get_timer_info_file();
         path.exists()
@@ -198,6 +392,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn test_time_left() {
         let now = chrono::Utc::now().timestamp();
         let mut timer_info = TimerInfo::default();
@@ -207,6 +402,49 @@ mod tests {
     }
 
     #[test]
+    fn test_timer_lock() -> AppResult<()> {
+        let lock = TimerLock::acquire()?;
+        assert!(get_timer_lock_file().exists());
+        drop(lock);
+        assert!(!get_timer_lock_file().exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_percentage() {
+        let now = chrono::Utc::now().timestamp();
+        let timer_info = TimerInfo {
+            start_time: now - 10,
+            duration: 20,
+            ..Default::default()
+        };
+        assert_eq!(timer_info.get_percentage(), 50.0);
+
+        let timer_info = TimerInfo {
+            duration: 0,
+            ..Default::default()
+        };
+        assert_eq!(timer_info.get_percentage(), 0.0);
+    }
+
+    #[test]
+    fn test_warning_due() {
+        let now = chrono::Utc::now().timestamp();
+        let mut timer_info = TimerInfo {
+            state: TimerState::Running,
+            start_time: now - 10,
+            duration: 70,
+            ..Default::default()
+        };
+        assert!(timer_info.is_warning_due(60));
+        assert!(!timer_info.is_warning_due(30));
+        assert!(!timer_info.is_warning_due(0));
+        timer_info.warned = true;
+        assert!(!timer_info.is_warning_due(60));
+    }
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn test_time_elapsed() {
         let now = chrono::Utc::now().timestamp();
         let mut timer_info = TimerInfo::default();
@@ -0,0 +1,323 @@
+use crate::config::TaskSource;
+use crate::error::*;
+use crate::task_file::{FileTask, LineFile, TaskFile};
+use crate::timer_info::TimerTask;
+use std::path::Path;
+
+/// Tag which counts the completed pomodoros of a task
+const POMODORO_TAG: &str = "pomo";
+/// Tag which holds the estimated pomodoros of a task
+const ESTIMATE_TAG: &str = "est";
+
+/// Defines a task line of a todo.txt file
+#[derive(Debug, Clone, PartialEq)]
+pub struct TodoItem {
+    /// Line number (starting from 1), which is used as the ID of the task
+    pub line: u32,
+    pub text: String,
+}
+
+impl TodoItem {
+    /// Return the priority (`(A) ` prefix) of an open task
+    pub fn priority(&self) -> Option<char> {
+        match self.text.as_bytes() {
+            [b'(', p @ b'A'..=b'Z', b')', b' ', ..] => Some(*p as char),
+            _ => None,
+        }
+    }
+
+    /// Return the value of the `key:value` tag
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.text
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix(key)?.strip_prefix(':'))
+    }
+
+    /// Return the description without the completion mark, priority, dates and `key:value` tags
+    pub fn description(&self) -> String {
+        let mut words = self.text.split_whitespace().peekable();
+        if self.is_done() || self.priority().is_some() {
+            words.next();
+        }
+        while words.peek().is_some_and(|word| is_date(word)) {
+            words.next();
+        }
+        words
+            .filter(|word| !is_tag(word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl FileTask for TodoItem {
+    fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Return true if the task is complete (`x ` prefix)
+    fn is_done(&self) -> bool {
+        self.text.starts_with("x ")
+    }
+
+    fn snapshot(&self) -> TimerTask {
+        let count = |key| self.tag(key).and_then(|value| value.parse().ok());
+        TimerTask {
+            id: self.line,
+            title: self.description(),
+            completed: count(POMODORO_TAG).unwrap_or(0),
+            estimate: count(ESTIMATE_TAG).unwrap_or(0),
+            source: TaskSource::TodoTxt,
+        }
+    }
+}
+
+/// Return true if the word is a date (`YYYY-MM-DD`)
+fn is_date(word: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok()
+}
+
+/// Return true if the word is a `key:value` tag (URLs are not tags)
+fn is_tag(word: &str) -> bool {
+    match word.split_once(':') {
+        Some((key, value)) => !key.is_empty() && !value.is_empty() && !value.starts_with("//"),
+        None => false,
+    }
+}
+
+/// Set the value of the `key:value` tag. The tag is appended if the line does not have it.
+pub fn set_tag(text: &str, key: &str, value: &str) -> String {
+    let prefix = format!("{}:", key);
+    let mut found = false;
+    let words = text
+        .split(' ')
+        .map(|word| match word.strip_prefix(&prefix) {
+            Some(_) if !found => {
+                found = true;
+                format!("{}{}", prefix, value)
+            }
+            _ => word.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let text = words.join(" ");
+    if found {
+        text
+    } else {
+        format!("{} {}{}", text.trim_end(), prefix, value)
+    }
+}
+
+/// Mark the line complete with the todo.txt `x <date>` syntax. The priority is kept as a
+/// `pri:` tag.
+pub fn complete(text: &str, date: &str) -> String {
+    let item = TodoItem {
+        line: 0,
+        text: text.to_string(),
+    };
+    if item.is_done() {
+        return text.to_string();
+    }
+    match item.priority() {
+        Some(priority) => format!(
+            "x {} {}",
+            date,
+            set_tag(&text[4..], "pri", &priority.to_string())
+        ),
+        None => format!("x {} {}", date, text),
+    }
+}
+
+/// Return the open task with the highest priority. Tasks without a priority come last, and the
+/// first line wins a tie.
+pub fn top_priority(items: &[TodoItem]) -> Option<&TodoItem> {
+    items
+        .iter()
+        .filter(|item| !item.is_done())
+        .min_by_key(|item| (item.priority().unwrap_or('~'), item.line))
+}
+
+/// Defines a todo.txt file
+#[derive(Debug)]
+pub struct TodoFile {
+    file: LineFile,
+}
+
+impl TodoFile {
+    /// Read the todo.txt file. A missing file is an empty task list.
+    pub fn open(path: &Path) -> AppResult<Self> {
+        Ok(Self {
+            file: LineFile::open(path)?,
+        })
+    }
+
+    /// Replace the text of the line
+    pub fn update(&mut self, line: u32, text: &str) {
+        self.file.update(line, text);
+    }
+}
+
+impl TaskFile for TodoFile {
+    type Item = TodoItem;
+
+    /// Return the task lines (blank lines are skipped)
+    fn items(&self) -> Vec<TodoItem> {
+        self.file
+            .lines()
+            .filter(|(_, text)| !text.trim().is_empty())
+            .map(|(line, text)| TodoItem {
+                line,
+                text: text.to_string(),
+            })
+            .collect()
+    }
+
+    /// Append a task with the creation date
+    fn add(&mut self, title: &str, estimate: u32) -> AppResult<u32> {
+        let mut text = format!("{} {}", chrono::Local::now().format("%Y-%m-%d"), title);
+        if estimate > 0 {
+            text = set_tag(&text, ESTIMATE_TAG, &estimate.to_string());
+        }
+        self.file.trim_blank_lines();
+        Ok(self.file.append(&text))
+    }
+
+    /// Set the `est:N` tag of the task
+    fn set_estimate(&mut self, line: u32, estimate: u32) -> AppResult<()> {
+        let item = self.get(line)?;
+        self.update(
+            line,
+            &set_tag(&item.text, ESTIMATE_TAG, &estimate.to_string()),
+        );
+        Ok(())
+    }
+
+    /// Mark the task complete with today's date
+    fn complete(&mut self, item: &TodoItem) {
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        self.update(item.line, &complete(&item.text, &date));
+    }
+
+    fn save(&self) -> AppResult<()> {
+        self.file.save()
+    }
+}
+
+/// Count a completed pomodoro in the `pomo:N` tag of the task. Returns the updated snapshot, or
+/// `None` if the task is no longer in the file.
+pub fn record_pomodoro(path: &Path, task: &TimerTask) -> AppResult<Option<TimerTask>> {
+    let mut file = TodoFile::open(path)?;
+    let Some(item) = file.find(task) else {
+        return Ok(None);
+    };
+    let count = item.snapshot().completed + 1;
+    let text = set_tag(&item.text, POMODORO_TAG, &count.to_string());
+    file.update(item.line, &text);
+    file.save()?;
+    Ok(Some(
+        TodoItem {
+            line: item.line,
+            text,
+        }
+        .snapshot(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str) -> TodoItem {
+        TodoItem {
+            line: 1,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_item() {
+        let task = item("(A) 2024-01-01 Write RFC +docs pomo:2 est:4 due:2024-02-01");
+        assert_eq!(task.priority(), Some('A'));
+        assert!(!task.is_done());
+        assert_eq!(task.description(), "Write RFC +docs");
+        let snapshot = task.snapshot();
+        assert_eq!((snapshot.completed, snapshot.estimate), (2, 4));
+
+        let task = item("x 2024-01-02 2024-01-01 Review https://example.com");
+        assert!(task.is_done());
+        assert_eq!(task.priority(), None);
+        assert_eq!(task.description(), "Review https://example.com");
+    }
+
+    #[test]
+    fn test_set_tag() {
+        assert_eq!(set_tag("Write RFC", "pomo", "1"), "Write RFC pomo:1");
+        assert_eq!(
+            set_tag("Write RFC pomo:1 @home", "pomo", "2"),
+            "Write RFC pomo:2 @home"
+        );
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(
+            complete("(B) 2024-01-01 Write RFC", "2024-01-05"),
+            "x 2024-01-05 2024-01-01 Write RFC pri:B"
+        );
+        assert_eq!(
+            complete("Write RFC", "2024-01-05"),
+            "x 2024-01-05 Write RFC"
+        );
+        assert_eq!(complete("x Done", "2024-01-05"), "x Done");
+    }
+
+    #[test]
+    fn test_find() {
+        let file = TodoFile {
+            file: LineFile::parse(Path::new("todo.txt"), "(A) New task\nWrite RFC pomo:1"),
+        };
+        let mut task = item("Write RFC").snapshot();
+        assert_eq!(file.find(&task).unwrap().line, 2);
+        task.title = "Write the RFC".to_string();
+        assert_eq!(file.find(&task), None);
+    }
+
+    #[test]
+    fn test_keep_line_endings() {
+        let mut file = TodoFile {
+            file: LineFile::parse(Path::new("todo.txt"), "(A) Write RFC\r\nReview\r\n\r\n"),
+        };
+        assert_eq!(file.get(1).unwrap().text, "(A) Write RFC");
+        file.update(1, "(A) Write RFC pomo:1");
+        let line = file.add("Deploy", 0).unwrap();
+        assert_eq!(line, 3);
+        assert_eq!(file.get(line).unwrap().description(), "Deploy");
+        assert!(file
+            .file
+            .contents()
+            .starts_with("(A) Write RFC pomo:1\r\nReview\r\n"));
+        assert!(file.file.contents().ends_with(" Deploy\r\n"));
+    }
+
+    #[test]
+    fn test_top_priority() {
+        let items = vec![
+            TodoItem {
+                line: 1,
+                text: "No priority".to_string(),
+            },
+            TodoItem {
+                line: 2,
+                text: "x (A) Done".to_string(),
+            },
+            TodoItem {
+                line: 3,
+                text: "(B) Second".to_string(),
+            },
+            TodoItem {
+                line: 4,
+                text: "(B) Later".to_string(),
+            },
+        ];
+        assert_eq!(top_priority(&items).unwrap().line, 3);
+        assert_eq!(top_priority(&items[..1]).unwrap().line, 1);
+    }
+}
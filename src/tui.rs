@@ -0,0 +1,319 @@
+use crate::app::*;
+use crate::args::TimeFormat;
+use crate::controls::Control;
+use crate::error::*;
+use crate::history::{interruptions_on, pomodoros_on, read_history, HistoryEntry};
+use crate::timer_info::{TimerInfo, TimerPhase, TimerState, DEFAULT_BREAK_DURATION};
+use crate::utils::convert_to_time_format;
+use chrono::{Local, TimeZone};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+use std::io::{Stdout, Write};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+/// Height of the block digits in rows
+const DIGIT_HEIGHT: usize = 5;
+
+/// Return the rows of the block glyph of the character
+fn glyph(c: char) -> [&'static str; DIGIT_HEIGHT] {
+    match c {
+        '0' => ["███", "█ █", "█ █", "█ █", "███"],
+        '1' => ["  █", "  █", "  █", "  █", "  █"],
+        '2' => ["███", "  █", "███", "█  ", "███"],
+        '3' => ["███", "  █", "███", "  █", "███"],
+        '4' => ["█ █", "█ █", "███", "  █", "  █"],
+        '5' => ["███", "█  ", "███", "  █", "███"],
+        '6' => ["███", "█  ", "███", "█ █", "███"],
+        '7' => ["███", "  █", "  █", "  █", "  █"],
+        '8' => ["███", "█ █", "███", "█ █", "███"],
+        '9' => ["███", "█ █", "███", "  █", "███"],
+        ':' => [" ", "█", " ", "█", " "],
+        _ => [" ", " ", " ", " ", " "],
+    }
+}
+
+/// Render the text with block digits. Every glyph column is doubled so that the digits look
+/// square in the terminal.
+pub fn render_big_text(text: &str) -> Vec<String> {
+    (0..DIGIT_HEIGHT)
+        .map(|row| {
+            text.chars()
+                .map(|c| {
+                    glyph(c)[row]
+                        .chars()
+                        .flat_map(|c| [c, c])
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+        .collect()
+}
+
+/// Render a progress gauge of the given width (including the brackets)
+pub fn render_gauge(fraction: f64, width: usize) -> String {
+    let inner = width.saturating_sub(2);
+    let filled = ((fraction.clamp(0.0, 1.0) * inner as f64).round() as usize).min(inner);
+    format!("[{}{}]", "█".repeat(filled), "░".repeat(inner - filled))
+}
+
+/// Return the one-line summary of the history entry
+fn format_entry(entry: &HistoryEntry) -> String {
+    let time = Local
+        .timestamp_opt(entry.start_time, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    // Interruptions are marked with `'` (internal) and `-` (external) as in the original technique
+    let marks = format!(
+        "{}{}",
+        "'".repeat(entry.internal_interruptions as usize),
+        "-".repeat(entry.external_interruptions as usize)
+    );
+    format!(
+        "{}  {:>8}  {:<5}  {}  {}{}{}",
+        time,
+        convert_to_time_format(entry.length(), TimeFormat::Digital),
+        format!("{:?}", entry.phase),
+        if entry.completed {
+            "✓"
+        } else if entry.void {
+            "⊘"
+        } else {
+            "✗"
+        },
+        marks,
+        if marks.is_empty() { "" } else { " " },
+        entry.message
+    )
+}
+
+/// Enters the alternate screen in raw mode for as long as the guard is alive
+struct TerminalGuard {
+    stdout: Stdout,
+}
+
+impl TerminalGuard {
+    fn new() -> AppResult<Self> {
+        let mut stdout = std::io::stdout();
+        enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, Hide)?;
+        Ok(Self { stdout })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(self.stdout, Show, LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Defines the state of the dashboard between redraws
+#[derive(Default)]
+struct Dashboard {
+    scroll: usize,
+    notice: String,
+}
+
+impl Dashboard {
+    /// Draw the whole dashboard to fit the terminal size
+    fn draw(
+        &self,
+        out: &mut Stdout,
+        timer_info: &TimerInfo,
+        history: &[HistoryEntry],
+    ) -> AppResult<()> {
+        let (cols, rows) = crossterm::terminal::size()?;
+        let (cols, rows) = (cols as usize, rows as usize);
+        let center = |text: &str| {
+            let len = text.chars().count();
+            cols.saturating_sub(len) / 2
+        };
+        let mut lines: Vec<(usize, String)> = Vec::new();
+
+        let state = match timer_info.state {
+            TimerState::Running => "Running",
+            TimerState::Paused => "Paused",
+            TimerState::Finished => "Finished",
+        };
+        lines.push((
+            0,
+            format!(" pomodoro-cli · {:?} · {}", timer_info.phase, state),
+        ));
+        lines.push((0, String::new()));
+
+        let time = convert_to_time_format(timer_info.get_time_left().max(0), TimeFormat::Digital);
+        if rows >= 16 && cols >= 50 {
+            for row in render_big_text(&time) {
+                lines.push((center(&row), row));
+            }
+        } else {
+            lines.push((center(&time), time));
+        }
+        lines.push((0, String::new()));
+
+        let fraction = if timer_info.duration > 0 {
+            timer_info.get_time_elapsed() as f64 / timer_info.duration as f64
+        } else {
+            1.0
+        };
+        let gauge = format!(
+            "{} {:>3}%",
+            render_gauge(fraction, cols.saturating_sub(10).min(60)),
+            (fraction.clamp(0.0, 1.0) * 100.0) as i64
+        );
+        lines.push((center(&gauge), gauge));
+        if !timer_info.message.is_empty() {
+            lines.push((center(&timer_info.message), timer_info.message.clone()));
+        }
+        lines.push((0, String::new()));
+
+        let today = Local::now().date_naive();
+        let (count, focus) = pomodoros_on(history, today);
+        let (internal, external) = interruptions_on(history, today);
+        lines.push((
+            1,
+            format!(
+                "Today: {} pomodoros · {} focus · {} interruptions ({} internal, {} external)",
+                count,
+                convert_to_time_format(focus, TimeFormat::Segmented),
+                internal + external,
+                internal,
+                external
+            ),
+        ));
+        lines.push((0, String::new()));
+        lines.push((1, "Recent sessions".to_string()));
+
+        let list_height = rows.saturating_sub(lines.len() + 2);
+        for entry in history.iter().rev().skip(self.scroll).take(list_height) {
+            lines.push((1, format_entry(entry)));
+        }
+
+        queue!(out, Clear(ClearType::All))?;
+        for (row, (column, text)) in lines.iter().enumerate().take(rows.saturating_sub(1)) {
+            let text: String = text.chars().take(cols.saturating_sub(*column)).collect();
+            queue!(out, MoveTo(*column as u16, row as u16))?;
+            write!(out, "{}", text)?;
+        }
+        let help = format!(
+            " [n] work [b] break [p] pause [s] stop [+/-] time [↑/↓] scroll [q] quit{}",
+            self.notice
+        );
+        let help: String = help.chars().take(cols).collect();
+        queue!(out, MoveTo(0, rows.saturating_sub(1) as u16))?;
+        write!(out, "{}", help)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Run the full-screen dashboard until the user quits.
+pub fn run_tui() -> AppResult<()> {
+    let quit = Arc::new(AtomicBool::new(false));
+    let flag = quit.clone();
+    ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst))
+        .map_err(|e| AppError::new(&format!("Cannot set the signal handler: {}", e)))?;
+
+    let mut terminal = TerminalGuard::new()?;
+    let mut dashboard = Dashboard::default();
+    // The alarm is played in a separate process so that the dashboard keeps responding and its
+    // messages do not end up on the screen. The errors are shown in the status line instead.
+    let (alarm_error_sender, alarm_errors) = mpsc::channel::<String>();
+    while !quit.load(Ordering::SeqCst) {
+        let timer_info = TimerInfo::from_file_or_default()?;
+        if timer_info.is_running() && timer_info.is_time_run_out() {
+            if finish_timer()?.is_none() {
+                continue;
+            }
+            let alarm = spawn_alarm(Stdio::piped())?;
+            let sender = alarm_error_sender.clone();
+            thread::spawn(move || {
+                let Ok(output) = alarm.wait_with_output() else {
+                    return;
+                };
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    let error = stderr.lines().last().unwrap_or("Unknown error");
+                    sender.send(error.to_string()).ok();
+                }
+            });
+            continue;
+        }
+        if let Ok(error) = alarm_errors.try_recv() {
+            dashboard.notice = format!("  (Alarm: {})", error);
+        }
+        check_warning(TimerInfo::from_file_or_default()?)?;
+        let history = read_history()?;
+        dashboard.scroll = dashboard.scroll.min(history.len().saturating_sub(1));
+        dashboard.draw(&mut terminal.stdout, &timer_info, &history)?;
+
+        if !event::poll(Duration::from_millis(1000))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            // Resize and other events just trigger a redraw
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        let result = match key.code {
+            KeyCode::Char('n') => start_timer(StartOptions::default()),
+            KeyCode::Char('b') => start_timer(StartOptions {
+                duration: Some(DEFAULT_BREAK_DURATION),
+                phase: TimerPhase::Break,
+                ..Default::default()
+            }),
+            KeyCode::Up | KeyCode::Char('k') => {
+                dashboard.scroll = dashboard.scroll.saturating_sub(1);
+                Ok(())
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                dashboard.scroll += 1;
+                Ok(())
+            }
+            _ => match Control::from_key(&key) {
+                Some(Control::Detach) => break,
+                Some(Control::PauseResume) => pause_timer(),
+                Some(Control::Stop) => stop_timer(),
+                Some(Control::AddTime(seconds)) => add_time(seconds),
+                Some(Control::EditMessage) | None => Ok(()),
+            },
+        };
+        dashboard.notice = match result {
+            Ok(()) => String::new(),
+            Err(e) => format!("  ({})", e),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_big_text() {
+        let rows = render_big_text("1:0");
+        assert_eq!(rows.len(), DIGIT_HEIGHT);
+        assert_eq!(rows[0], "    ██      ██████");
+        assert_eq!(rows[1], "    ██  ██  ██  ██");
+    }
+
+    #[test]
+    fn test_render_gauge() {
+        assert_eq!(render_gauge(0.5, 6), "[██░░]");
+        assert_eq!(render_gauge(2.0, 4), "[██]");
+        assert_eq!(render_gauge(0.0, 1), "[]");
+    }
+}
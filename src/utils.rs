@@ -1,5 +1,8 @@
 use crate::args::TimeFormat;
-use std::path::PathBuf;
+use crate::error::*;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Return the path to the timer information file. This is the cache directory on Linux and
 /// LocalAppData on Windows. In case the cache directory is not available, the current
@@ -10,17 +13,180 @@ pub fn get_timer_info_file() -> PathBuf {
     path
 }
 
-/// Return the path to the custom audio file for the alarm. This is the config directory on Linux and RoamingAppData on Windows.
+/// Return the path to the lock file which is held while the timer is started or ended
+pub fn get_timer_lock_file() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("pomodoro-cli-info.lock");
+    path
+}
+
+/// Return the path to the file which marks a pending screen lock. The file contains the
+/// timestamp when the screen is going to be locked.
+pub fn get_pending_lock_file() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("pomodoro-cli-lock-pending");
+    path
+}
+
+/// Return the path to the file which tells whether the focus mode (do not disturb) is enabled
+pub fn get_focus_mode_file() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("pomodoro-cli-focus.json");
+    path
+}
+
+/// Return the path to the file which lists the processes stopped by the process guard
+pub fn get_stopped_processes_file() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("pomodoro-cli-stopped.json");
+    path
+}
+
+/// Return the path to the file which contains the process ID of the running process guard
+pub fn get_process_guard_file() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("pomodoro-cli-guard.pid");
+    path
+}
+
+/// Return the path to the event log (JSON lines) which is followed by the `subscribe` command
+pub fn get_event_log_file() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("pomodoro-cli-events.jsonl");
+    path
+}
+
+/// Return the default path of the event socket. This is the runtime directory on Linux and the
+/// cache directory elsewhere.
+pub fn get_event_socket_file() -> PathBuf {
+    let mut path = dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    path.push("pomodoro-cli-events.sock");
+    path
+}
+
+/// Return the path to the session history file. This is the data directory on Linux and
+/// RoamingAppData on Windows. In case the data directory is not available, the cache directory
+/// is used.
+pub fn get_history_file() -> PathBuf {
+    let mut path = dirs::data_dir()
+        .map(|path| path.join("pomodoro-cli"))
+        .or_else(dirs::cache_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    path.push("history.jsonl");
+    path
+}
+
+/// Return the path of the task store (next to the history in the data directory)
+pub fn get_task_store_file() -> PathBuf {
+    get_history_file().with_file_name("tasks.json")
+}
+
+/// Return the path to the file which stores the emergency skips used today.
+pub fn get_skip_tokens_file() -> PathBuf {
+    let mut path = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("pomodoro-cli-skips.json");
+    path
+}
+
+/// Run the command line with the system shell and wait for it to finish.
+pub fn run_shell_command(command: &str) -> AppResult<()> {
+    let status = shell_command(command).status()?;
+    if !status.success() {
+        return Err(AppError::new(&format!(
+            "Command `{}` failed ({})",
+            command, status
+        )));
+    }
+    Ok(())
+}
+
+/// Run the command line with the system shell and return its standard output.
+pub fn shell_command_output(command: &str) -> AppResult<String> {
+    let output = shell_command(command).output()?;
+    if !output.status.success() {
+        return Err(AppError::new(&format!(
+            "Command `{}` failed ({})",
+            command, output.status
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Return the command which runs the command line with the system shell
+fn shell_command(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        Command::new("cmd")
+    } else {
+        Command::new("sh")
+    };
+    shell.args([if cfg!(windows) { "/C" } else { "-c" }, command]);
+    shell
+}
+
+/// Supported file extensions for the custom sound files
+pub const SOUND_FILE_EXTENSIONS: [&str; 4] = ["mp3", "ogg", "wav", "flac"];
+
+/// Return the path to the application config directory. This is the config directory on Linux
+/// and RoamingAppData on Windows.
+pub fn get_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|path| path.join("pomodoro-cli"))
+}
+
+/// Return the path to the JSON configuration file.
+pub fn get_config_file() -> Option<PathBuf> {
+    get_config_dir().map(|path| path.join("config.json"))
+}
+
+/// Return the path to a custom sound file with the given name (without extension) in the config
+/// directory. Any of the `SOUND_FILE_EXTENSIONS` is accepted. In case the sound file is not
+/// found, `None` is returned.
+pub fn get_custom_sound_file(name: &str) -> Option<PathBuf> {
+    let dir = get_config_dir()?;
+    SOUND_FILE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.exists())
+}
+
+/// Return the path to the custom audio file for the alarm (e.g. `alarm.mp3` or `alarm.ogg`).
 /// In case the audio file is not found, `None` is returned.
 pub fn get_custom_alarm_file() -> Option<PathBuf> {
-    if let Some(mut path) = dirs::config_dir() {
-        path.push("pomodoro-cli");
-        path.push("alarm.mp3");
-        if path.exists() {
-            return Some(path);
+    get_custom_sound_file("alarm")
+}
+
+/// Replace the file atomically by writing a temporary file next to it and renaming it over the
+/// file. A symlink is followed, so that the file it points to is replaced. Every write uses its
+/// own temporary file, so that concurrent writers (other processes or threads) do not mix.
+pub fn write_file_atomically(path: &Path, contents: &str) -> AppResult<()> {
+    static WRITE_COUNT: AtomicUsize = AtomicUsize::new(0);
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(
+        ".pomodoro-cli.{}.{}.tmp",
+        std::process::id(),
+        WRITE_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = std::fs::write(&tmp_path, contents).and_then(|_| {
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            std::fs::set_permissions(&tmp_path, metadata.permissions())?;
         }
+        std::fs::rename(&tmp_path, &path)
+    });
+    if let Err(e) = result {
+        std::fs::remove_file(&tmp_path).ok();
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Expand the leading `~` of the path to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
     }
-    None
 }
 
 /// Return the path to the custom icon file for the notification. This is the config directory on Linux and RoamingAppData on Windows.
@@ -95,7 +261,7 @@ fn get_time_segments(seconds: i64) -> (i64, i64, i64) {
     seconds -= hours * 3600;
     let minutes = (seconds % 3600) / 60;
     seconds -= minutes * 60;
-    return (hours, minutes, seconds);
+    (hours, minutes, seconds)
 }
 
 /// Return the seconds in segmented time format (e.g. 1h 30m 10s)
@@ -107,30 +273,30 @@ fn convert_to_segmented_format(seconds: i64) -> String {
     }
     if minutes > 0 {
         if !time.is_empty() {
-            time.push_str(" ");
+            time.push(' ');
         }
         time.push_str(&format!("{}m", minutes));
     }
     if seconds > 0 {
         if !time.is_empty() {
-            time.push_str(" ");
+            time.push(' ');
         }
         time.push_str(&format!("{}s", seconds));
     }
     if time.is_empty() {
         time.push_str("0s");
     }
-    return time;
+    time
 }
 
 /// Return the seconds in digit format (e.g. 01:30:10)
 fn convert_to_digital_format(seconds: i64) -> String {
     let (hours, minutes, seconds) = get_time_segments(seconds);
-    return if hours > 0 {
+    if hours > 0 {
         format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
     } else {
         format!("{:02}:{:02}", minutes, seconds)
-    };
+    }
 }
 
 pub fn convert_to_time_format(seconds: i64, time_format: TimeFormat) -> String {
@@ -177,4 +343,14 @@ mod tests {
         assert_eq!(convert_to_digital_format(10), "00:10");
         assert_eq!(convert_to_digital_format(0), "00:00");
     }
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~/alarm.ogg"), home.join("alarm.ogg"));
+        assert_eq!(
+            expand_home("/tmp/alarm.ogg"),
+            PathBuf::from("/tmp/alarm.ogg")
+        );
+    }
 }
@@ -0,0 +1,168 @@
+use crate::config::{Config, WebhookConfig};
+use crate::error::*;
+use crate::events::EventKind;
+use serde_json::Value;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Send the event to the webhooks which subscribe to it. The requests are sent by a background
+/// process so that the caller (e.g. `status`) is never blocked by a slow or unreachable server.
+/// Every event has its own process, so the order of delivery is not guaranteed.
+pub fn dispatch_webhooks(event: EventKind, event_json: &str) -> AppResult<()> {
+    let config = Config::from_file_or_default()?;
+    if !config.webhooks.iter().any(|webhook| webhook.accepts(event)) {
+        return Ok(());
+    }
+    let mut child = Command::new(std::env::current_exe()?)
+        .args(["send-webhooks", event_json])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()?;
+    // Reap the process so that long-running commands (e.g. `serve`) do not accumulate zombies
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Send the event (JSON) to every webhook which subscribes to it. Used by the detached process
+/// spawned by `dispatch_webhooks`.
+pub fn send_webhooks(event_json: &str) -> AppResult<()> {
+    let config = Config::from_file_or_default()?;
+    let event: Value = serde_json::from_str(event_json)?;
+    let kind: EventKind = serde_json::from_value(event["event"].clone())?;
+    let handles: Vec<_> = config
+        .webhooks
+        .into_iter()
+        .filter(|webhook| webhook.accepts(kind))
+        .map(|webhook| {
+            let event = event.clone();
+            thread::spawn(move || {
+                if let Err(e) = send_webhook(&webhook, &event) {
+                    eprintln!("Warning: Webhook {} failed: {}", webhook.url, e);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        let _ = handle.join();
+    }
+    Ok(())
+}
+
+/// POST the event to the webhook. Failed requests are retried with an exponential backoff.
+pub fn send_webhook(webhook: &WebhookConfig, event: &Value) -> AppResult<()> {
+    let body = match &webhook.body {
+        Some(template) => render_template(template, event),
+        None => event.to_string(),
+    };
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(webhook.timeout))
+        .build();
+
+    let mut delay = Duration::from_secs(1);
+    let mut attempt = 0;
+    loop {
+        let mut request = agent
+            .post(&webhook.url)
+            .set("Content-Type", &webhook.content_type);
+        for (name, value) in &webhook.headers {
+            request = request.set(name, value);
+        }
+        match request.send_string(&body) {
+            Ok(_) => return Ok(()),
+            Err(e) if attempt >= webhook.retries => return Err(AppError::new(&e.to_string())),
+            Err(_) => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Replace the `{{name}}` placeholders with the fields of the event. The fields of the timer
+/// snapshot (e.g. `{{message}}`, `{{phase}}`) can be used directly. Strings are escaped so that
+/// they can be placed inside JSON strings.
+pub fn render_template(template: &str, event: &Value) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let name = rest[start + 2..start + end].trim();
+        let value = event.get(name).or_else(|| event["timer"].get(name));
+        match value {
+            Some(Value::String(text)) => {
+                let quoted = Value::String(text.clone()).to_string();
+                output.push_str(&quoted[1..quoted.len() - 1]);
+            }
+            Some(value) => output.push_str(&value.to_string()),
+            None => output.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event() -> Value {
+        json!({
+            "event": "started",
+            "remaining": 1500,
+            "timer": { "state": "Running", "message": "Say \"hi\"", "phase": "Work" }
+        })
+    }
+
+    #[test]
+    fn test_render_template() {
+        let body = render_template(
+            r#"{"on": {{remaining}}, "text": "{{ message }} ({{event}}) {{unknown}}"}"#,
+            &event(),
+        );
+        assert_eq!(
+            body,
+            r#"{"on": 1500, "text": "Say \"hi\" (started) {{unknown}}"}"#
+        );
+    }
+
+    #[test]
+    fn test_send_webhook() -> AppResult<()> {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr());
+        let received = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let path = request.url().to_string();
+            request.respond(tiny_http::Response::empty(200)).unwrap();
+            (path, body)
+        });
+
+        let webhook = WebhookConfig {
+            url,
+            body: Some("{{phase}} {{remaining}}".to_string()),
+            ..Default::default()
+        };
+        send_webhook(&webhook, &event())?;
+        let (path, body) = received.join().unwrap();
+        assert_eq!(path, "/hook");
+        assert_eq!(body, "Work 1500");
+        Ok(())
+    }
+
+    #[test]
+    fn test_webhook_events() {
+        let mut webhook = WebhookConfig::default();
+        assert!(!webhook.accepts(EventKind::Started));
+        webhook.url = "http://localhost/hook".to_string();
+        assert!(webhook.accepts(EventKind::Started));
+        assert!(!webhook.accepts(EventKind::Tick));
+    }
+}